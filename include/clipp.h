@@ -0,0 +1,26 @@
+/* clipp, from C. build with `cargo rustc --release --lib --crate-type cdylib --features ffi` */
+#ifndef CLIPP_H
+#define CLIPP_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* copy a nul terminated UTF-8 string. 0 if it worked, -1 if it didn't. */
+int clipp_copy(const char *text);
+
+/* paste, as a nul terminated UTF-8 string to give back to clipp_free.
+ * NULL if it didn't work, or the text holds a nul. */
+char *clipp_paste(void);
+
+/* empty the clipboard. 0 if it worked, -1 if it didn't. */
+int clipp_clear(void);
+
+/* free a string from clipp_paste. does nothing with NULL. */
+void clipp_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,192 @@
+//! async copy and paste, on tokio
+use crate::{
+    providers::{check, fits, past_limit, Op, Provider},
+    text::{endings, endings_owned},
+    ClipError, ClipboardEvent, Result, Selection, Subscription,
+};
+use futures_core::Stream;
+use std::{
+    future::Future,
+    pin::Pin,
+    process::Stdio,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    process::{Child, Command},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    task::spawn_blocking,
+};
+
+async fn provider() -> Result<Arc<dyn Provider>> {
+    if let Some(c) = crate::testing::mocked() {
+        return Ok(c.shared());
+    }
+    let c = match crate::CLIP.get() {
+        Some(c) => c.as_ref().ok_or(ClipError::NoBackend)?,
+        // detection spawns things
+        None => spawn_blocking(crate::global).await.expect("detection ok")?,
+    };
+    Ok(c.shared())
+}
+
+/// give up on `f` if it takes longer than `op` may. the command is killed when it's dropped.
+async fn within<T>(op: Op, f: impl Future<Output = Result<T>>) -> Result<T> {
+    match crate::providers::timeout(op) {
+        Some(t) => tokio::time::timeout(t, f)
+            .await
+            .unwrap_or(Err(ClipError::Timeout)),
+        None => f.await,
+    }
+}
+
+/// wait for `ch`, and what it said on stderr if it failed
+async fn finish(ch: &mut Child) -> Result<()> {
+    let status = ch.wait().await.map_err(ClipError::Io)?;
+    let mut err = vec![];
+    if let (false, Some(e)) = (status.success(), &mut ch.stderr) {
+        // something in the background may hold it open
+        _ = tokio::time::timeout(Duration::from_millis(100), e.read_to_end(&mut err)).await;
+    }
+    check(status, || err)
+}
+
+/// Copy text to the clipboard, without blocking the runtime.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the clipboard backend fails.
+///
+/// # Panics
+///
+/// If called outside of a tokio runtime.
+pub async fn copy_async(text: &str) -> Result<()> {
+    let text = &*endings(Op::Copy, text);
+    fits(text.len())?;
+    let b = provider().await?;
+    let Some(c) = b.command(Selection::Clipboard, Op::Copy) else {
+        let text = text.to_owned();
+        return spawn_blocking(move || b.copy(Selection::Clipboard, &text))
+            .await
+            .expect("copy ok");
+    };
+    let mut ch = Command::from(c)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(ClipError::Spawn)?;
+    within(Op::Copy, async {
+        let mut stdin = ch.stdin.take().unwrap();
+        stdin
+            .write_all(text.as_bytes())
+            .await
+            .map_err(ClipError::Io)?;
+        drop(stdin);
+        finish(&mut ch).await
+    })
+    .await
+}
+
+/// Paste text from the clipboard, without blocking the runtime.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the clipboard backend fails.
+///
+/// # Panics
+///
+/// If called outside of a tokio runtime.
+pub async fn paste_async() -> Result<String> {
+    let b = provider().await?;
+    let Some(c) = b.command(Selection::Clipboard, Op::Paste) else {
+        let s = spawn_blocking(move || b.paste(Selection::Clipboard))
+            .await
+            .expect("paste ok")?;
+        fits(s.len())?;
+        return Ok(endings_owned(Op::Paste, s));
+    };
+    let mut ch = Command::from(c)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(ClipError::Spawn)?;
+    let out = within(Op::Paste, async {
+        let mut out = vec![];
+        // dropping it over the limit kills it, so the rest is never read
+        ch.stdout
+            .take()
+            .unwrap()
+            .take(past_limit())
+            .read_to_end(&mut out)
+            .await
+            .map_err(ClipError::Io)?;
+        fits(out.len())?;
+        finish(&mut ch).await?;
+        Ok(out)
+    })
+    .await?;
+    String::from_utf8(b.tidy(out))
+        .map(|s| endings_owned(Op::Paste, s))
+        .map_err(ClipError::Utf8)
+}
+
+/// clipboard changes, off [`Subscription`]'s thread
+struct Events {
+    rx: UnboundedReceiver<ClipboardEvent>,
+    _sub: Subscription,
+}
+
+impl Stream for Events {
+    type Item = ClipboardEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ClipboardEvent>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// A [`Stream`] of clipboard changes, to `select!` on alongside everything else.
+/// Watching stops when it's dropped. See [`on_change`](crate::on_change).
+///
+/// ```no_run
+/// let events = clipp::events();
+/// // tokio::select! on it with tokio_stream::StreamExt::next
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or the watcher thread can't be started. See [`try_events`].
+pub fn events() -> impl Stream<Item = ClipboardEvent> {
+    try_events().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// A [`Stream`] of clipboard changes. See [`events`].
+///
+/// # Errors
+///
+/// If no clipboard is available, or the watcher thread can't be started.
+pub fn try_events() -> Result<impl Stream<Item = ClipboardEvent>> {
+    let (tx, rx) = unbounded_channel();
+    let sub = crate::current()?.on_change(move |e| _ = tx.send(e))?;
+    Ok(Events { rx, _sub: sub })
+}
+
+#[test]
+fn stream() {
+    crate::testing::with_mock(|mock| {
+        let mut events = try_events().unwrap();
+        mock.set_text("changed");
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let next = rt.block_on(async {
+            let next = std::future::poll_fn(|cx| Pin::new(&mut events).poll_next(cx));
+            tokio::time::timeout(Duration::from_secs(5), next).await
+        });
+        assert_eq!(next.unwrap().unwrap().text, "changed");
+    });
+}
@@ -0,0 +1,140 @@
+//! naming the clipboard providers
+use crate::ClipError;
+use std::{fmt, str::FromStr};
+
+/// A clipboard provider, for [`force_backend`](crate::force_backend), `CLIPP_BACKEND` and `CLIPP_FALLBACK`.
+///
+/// Every backend is named here, but only the ones built for this platform (and enabled by the features) can be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    /// The win32 clipboard, `windows`.
+    Windows,
+    /// PowerShell's `Set-Clipboard` and `Get-Clipboard`, `powershell`.
+    PowerShell,
+    /// `NSPasteboard`, `nspasteboard`.
+    Pasteboard,
+    /// `pbcopy` and `pbpaste`, `pbcopy`.
+    PbCopy,
+    /// Haiku's `clipboard` command, `haiku`.
+    Haiku,
+    /// The windows clipboard from inside WSL, `wsl`.
+    Wsl,
+    /// The xdg desktop portal, `portal`.
+    Portal,
+    /// Wayland, natively, `wayland`.
+    Wayland,
+    /// `wl-copy` and `wl-paste`, `wl-copy`.
+    WlCopy,
+    /// X11, natively, `x11`.
+    X11,
+    /// `xsel`.
+    XSel,
+    /// `xclip`.
+    XClip,
+    /// KDE's klipper, over D-Bus (or `qdbus`, without `klipper-native`), `klipper`.
+    Klipper,
+    /// The copyq clipboard manager, `copyq`.
+    CopyQ,
+    /// tmux buffers, `tmux`.
+    Tmux,
+    /// GNU screen's paste buffer, `screen`.
+    Screen,
+    /// The lemonade client, for a lemonade server across ssh, `lemonade`.
+    Lemonade,
+    /// The clipper daemon, on `~/.clipper.sock` or port 8377, `clipper`. Can't paste.
+    Clipper,
+    /// The terminal, with OSC 52 escapes, `osc52`. Can't paste.
+    Osc52,
+    /// The browser's `navigator.clipboard`, `web`. Only pastes through [`paste_async`](crate::paste_async).
+    Web,
+    /// The clippd daemon, whenever it's running, `clippd`. See [`clippd`](crate::clippd).
+    Clippd,
+    /// A clipboard that only lives in this process, `memory`.
+    /// Never detected, so pick it with `CLIPP_BACKEND` or `CLIPP_FALLBACK`.
+    Memory,
+}
+
+impl Backend {
+    /// Every backend.
+    pub const ALL: [Self; 22] = [
+        Self::Windows,
+        Self::PowerShell,
+        Self::Pasteboard,
+        Self::PbCopy,
+        Self::Haiku,
+        Self::Wsl,
+        Self::Portal,
+        Self::Wayland,
+        Self::WlCopy,
+        Self::X11,
+        Self::XSel,
+        Self::XClip,
+        Self::Klipper,
+        Self::CopyQ,
+        Self::Tmux,
+        Self::Screen,
+        Self::Lemonade,
+        Self::Clipper,
+        Self::Osc52,
+        Self::Web,
+        Self::Clippd,
+        Self::Memory,
+    ];
+
+    /// The name of this backend, as `CLIPP_BACKEND` takes it.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Windows => "windows",
+            Self::PowerShell => "powershell",
+            Self::Pasteboard => "nspasteboard",
+            Self::PbCopy => "pbcopy",
+            Self::Haiku => "haiku",
+            Self::Wsl => "wsl",
+            Self::Portal => "portal",
+            Self::Wayland => "wayland",
+            Self::WlCopy => "wl-copy",
+            Self::X11 => "x11",
+            Self::XSel => "xsel",
+            Self::XClip => "xclip",
+            Self::Klipper => "klipper",
+            Self::CopyQ => "copyq",
+            Self::Tmux => "tmux",
+            Self::Screen => "screen",
+            Self::Lemonade => "lemonade",
+            Self::Clipper => "clipper",
+            Self::Osc52 => "osc52",
+            Self::Web => "web",
+            Self::Clippd => "clippd",
+            Self::Memory => "memory",
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = ClipError;
+
+    /// Parses a backend [`name`](Backend::name), ignoring case.
+    fn from_str(s: &str) -> Result<Self, ClipError> {
+        Self::ALL
+            .into_iter()
+            .find(|b| b.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or(ClipError::NoBackend)
+    }
+}
+
+#[test]
+fn names() {
+    for b in Backend::ALL {
+        assert_eq!(b.name().parse::<Backend>().unwrap(), b);
+    }
+    assert_eq!(" XSel".parse::<Backend>().unwrap(), Backend::XSel);
+    assert!("xclipp".parse::<Backend>().is_err());
+}
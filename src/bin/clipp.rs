@@ -0,0 +1,99 @@
+//! clipboard from the shell. `cargo install clipp --features cli`
+use clipp::{Clipboard, Selection};
+use std::{
+    io::{Read, Write},
+    process::ExitCode,
+};
+
+const USAGE: &str = "usage: clipp [-p] <command>
+
+commands:
+    copy [text]   copy text, or stdin if none is given
+    paste         print the clipboard
+    clear         empty the clipboard
+    watch         print the clipboard each time it changes, one per line
+    backend       print the backend in use, and why
+    sync listen <addr>
+                  share the clipboard with everything that connects (with the sync feature)
+    sync connect [addr]
+                  share the clipboard with a sync listen elsewhere, until it hangs up.
+                  without an address, the first one found on the network (with the mdns feature).
+                  both ends encrypt with CLIPP_SYNC_KEY, if it's set
+
+options:
+    -p, --primary  use the primary selection (x11 and wayland)
+    -0             separate entries with NUL in watch, instead of newlines
+    --seat <name>  use this wayland seat";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("clipp: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let mut sel = Selection::Clipboard;
+    let mut sep = '\n';
+    let mut rest = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(a) = args.next() {
+        match &*a {
+            "-p" | "--primary" => sel = Selection::Primary,
+            "--seat" => clipp::set_seat(Some(&args.next().ok_or(USAGE)?)),
+            "-0" => sep = '\0',
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ => rest.push(a),
+        }
+    }
+    let Some((cmd, args)) = rest.split_first() else {
+        return Err(USAGE.into());
+    };
+    let clip = Clipboard::new()?;
+    // we're about to exit
+    let copy = |text: &str| match sel {
+        Selection::Clipboard => clip.copy_persistent(text),
+        _ => clip.copy_to(sel, text),
+    };
+    match (&**cmd, args) {
+        ("copy", []) => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            copy(&text)?;
+        }
+        ("copy", text) => copy(&text.join(" "))?,
+        ("paste", []) => {
+            let mut out = std::io::stdout().lock();
+            out.write_all(clip.paste_from(sel)?.as_bytes())?;
+            out.flush()?;
+        }
+        ("clear", []) => clip.clear_selection(sel)?,
+        ("watch", []) => {
+            let mut out = std::io::stdout().lock();
+            for text in clip.watch_from(sel) {
+                write!(out, "{text}{sep}")?;
+                out.flush()?;
+            }
+        }
+        ("backend", []) => println!("{}: {}", clip.backend_name(), clip.backend_reason()),
+        #[cfg(all(feature = "sync", not(target_family = "wasm")))]
+        ("sync", [how, addr]) if how == "listen" => clipp::sync::listen(addr.as_str())?,
+        #[cfg(all(feature = "sync", not(target_family = "wasm")))]
+        ("sync", [how, addr]) if how == "connect" => clipp::sync::connect(addr.as_str())?.wait()?,
+        #[cfg(all(feature = "mdns", not(target_family = "wasm")))]
+        ("sync", [how]) if how == "connect" => {
+            let found = clipp::sync::discover(std::time::Duration::from_secs(3))?;
+            let peer = found.first().ok_or("no sync listen found on the network")?;
+            eprintln!("clipp: connecting to {}", peer.name);
+            clipp::sync::connect(&peer.addrs[..])?.wait()?;
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
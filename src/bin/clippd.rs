@@ -0,0 +1,38 @@
+//! the clipboard daemon, see `clipp::clippd`. `cargo install clipp --features clippd`
+use std::process::ExitCode;
+
+#[cfg(unix)]
+const USAGE: &str = "usage: clippd [--restore]
+
+options:
+    --restore  save the clipboard as it changes, and put it back when starting";
+
+#[cfg(unix)]
+fn main() -> ExitCode {
+    for a in std::env::args().skip(1) {
+        match &*a {
+            "--restore" => clipp::clippd::set_restore(true),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    match clipp::clippd::serve() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("clippd: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn main() -> ExitCode {
+    eprintln!("clippd: only on unix");
+    ExitCode::FAILURE
+}
@@ -0,0 +1,1495 @@
+//! clipboard handles
+use crate::{
+    providers::{self, Chosen},
+    text::{endings, endings_owned},
+    watch::hash,
+    Backend, ChangeToken, ClipError, ClipGuard, ClipboardEvent, ClipboardSnapshot, FileOp, Html,
+    Item, Lazy, Op, ParseOrClipError, Provider, Result, Selection, SelectionSync, Subscription,
+    Watch,
+};
+use std::{
+    borrow::Cow,
+    ffi::OsString,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+/// what marks a copy as a secret, per platform
+#[cfg(target_os = "macos")]
+const CONCEALED: &[(&str, &[u8])] = &[("org.nspasteboard.ConcealedType", b"")];
+#[cfg(target_family = "windows")]
+const CONCEALED: &[(&str, &[u8])] = &[
+    ("ExcludeClipboardContentFromMonitorProcessing", b"\0"),
+    // DWORD 0, to keep it out of win+v and the cloud
+    ("CanIncludeInClipboardHistory", &[0; 4]),
+    ("CanUploadToCloudClipboard", &[0; 4]),
+];
+#[cfg(not(any(target_os = "macos", target_family = "windows")))]
+const CONCEALED: &[(&str, &[u8])] = &[("x-kde-passwordManagerHint", b"secret")];
+
+/// what keeps a copy out of the history, and off other devices, for [`CopyOptions`]
+#[cfg(target_family = "windows")]
+const NO_HISTORY: &[(&str, &[u8])] = &[("CanIncludeInClipboardHistory", &[0; 4])];
+#[cfg(target_family = "windows")]
+const NO_CLOUD: &[(&str, &[u8])] = &[("CanUploadToCloudClipboard", &[0; 4])];
+#[cfg(not(target_family = "windows"))]
+const NO_HISTORY: &[(&str, &[u8])] = CONCEALED;
+#[cfg(not(target_family = "windows"))]
+const NO_CLOUD: &[(&str, &[u8])] = &[];
+
+/// Where a copy may go besides the clipboard. See [`Clipboard::copy_with`].
+/// Both are allowed by default.
+///
+/// ```no_run
+/// let only_here = clipp::CopyOptions {
+///     cloud: false,
+///     ..Default::default()
+/// };
+/// clipp::copy_with("a one time code", only_here);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CopyOptions {
+    /// Whether it may go in clipboard history: win+v's (`CanIncludeInClipboardHistory`), klipper's
+    /// and other managers' (as with [`copy_sensitive`](Clipboard::copy_sensitive)), and clipp's [`history`](crate::history).
+    pub history: bool,
+    /// Whether windows may sync it to the user's other devices (`CanUploadToCloudClipboard`).
+    /// Nothing else has a cloud clipboard to keep it from.
+    pub cloud: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            history: true,
+            cloud: true,
+        }
+    }
+}
+
+/// A handle to a clipboard. The free functions use a shared one, detected on first use.
+///
+/// A detected clipboard falls back to the next best backend when its backend breaks,
+/// i.e. the command went missing or the compositor restarted. Clones share the backend in use.
+///
+/// ```
+/// let clip = clipp::Clipboard::new()?;
+/// clip.copy("wow such clipboard")?;
+/// assert_eq!(clip.paste()?, "wow such clipboard");
+/// # Ok::<(), clipp::ClipError>(())
+/// ```
+#[derive(Clone)]
+pub struct Clipboard {
+    live: Arc<Mutex<Live>>,
+}
+
+/// the backend in use, and the ones that broke before it
+struct Live {
+    chosen: Chosen,
+    failed: Vec<&'static str>,
+}
+
+impl std::fmt::Debug for Clipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Clipboard")
+            .field("backend", &self.chosen().name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Clipboard {
+    pub(crate) fn of(chosen: Chosen) -> Self {
+        event!(info, "using {}, as {}", chosen.name, chosen.why);
+        Self {
+            live: Arc::new(Mutex::new(Live {
+                chosen,
+                failed: vec![],
+            })),
+        }
+    }
+
+    /// The clipboard the free functions use, detecting it if that hasn't happened yet.
+    ///
+    /// # Errors
+    ///
+    /// If no clipboard is available.
+    pub fn new() -> Result<Self> {
+        crate::current()
+    }
+
+    /// A clipboard using `backend`, instead of detecting one.
+    ///
+    /// # Errors
+    ///
+    /// If `backend` isn't available here.
+    pub fn with_backend(backend: Backend) -> Result<Self> {
+        Chosen::new(backend, "Clipboard::with_backend was called")
+            .map(Self::of)
+            .ok_or(ClipError::NoBackend)
+    }
+
+    /// A clipboard on another display than the one in the environment: an x11 one if it has a colon,
+    /// i.e. `":1"`, and otherwise a wayland socket, i.e. `"wayland-1"`. For nested compositors, and tests driving them.
+    ///
+    /// Uses `xclip`, `xsel`, or the native x11 backend for x11, and `wl-copy` for wayland.
+    ///
+    /// # Errors
+    ///
+    /// If none of those are available.
+    pub fn on_display(display: &str) -> Result<Self> {
+        providers::on_display(display)
+            .map(Self::of)
+            .ok_or(ClipError::NoBackend)
+    }
+
+    /// The find pasteboard, which holds what's being searched for across apps. See [`named`](Self::named).
+    pub const FIND: &str = "Apple CFPasteboard find";
+    /// The drag pasteboard, which holds what's being dragged. See [`named`](Self::named).
+    pub const DRAG: &str = "Apple CFPasteboard drag";
+
+    /// A macos pasteboard other than the general one, by name: [`FIND`](Self::FIND), [`DRAG`](Self::DRAG),
+    /// or one of your own, i.e. `"com.myapp.scratch"`, for apps to pass things between them without touching the clipboard.
+    ///
+    /// ```no_run
+    /// let scratch = clipp::Clipboard::named("com.myapp.scratch")?;
+    /// scratch.copy("for the other app")?;
+    /// # Ok::<(), clipp::ClipError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [`ClipError::NoBackend`] anywhere but macos with `macos-native`.
+    pub fn named(name: &str) -> Result<Self> {
+        providers::named(name)
+            .map(Self::of)
+            .ok_or(ClipError::NoBackend)
+    }
+
+    /// A clipboard using your own [`Provider`].
+    pub fn with_provider(provider: impl Provider + 'static) -> Self {
+        Self::of(Chosen {
+            clip: Arc::new(provider),
+            name: "custom",
+            why: "a provider was given",
+            falls_back: false,
+        })
+    }
+
+    fn live(&self) -> MutexGuard<'_, Live> {
+        // nothing panics halfway through changing it
+        self.live.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn chosen(&self) -> Chosen {
+        self.live().chosen.clone()
+    }
+
+    pub(crate) fn shared(&self) -> Arc<dyn Provider> {
+        self.chosen().clip
+    }
+
+    /// run `f` on the backend, falling back to the next one while it breaks
+    pub(crate) fn run<T>(&self, mut f: impl FnMut(&dyn Provider) -> Result<T>) -> Result<T> {
+        loop {
+            let c = self.chosen();
+            match f(&*c.clip) {
+                Err(e) if c.falls_back && e.broken() && self.fall_back(c.name, &e) => {}
+                r => return r,
+            }
+        }
+    }
+
+    /// give up on `name` for the next backend. false if there isn't one
+    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+    fn fall_back(&self, name: &'static str, why: &ClipError) -> bool {
+        let mut live = self.live();
+        if live.chosen.name != name {
+            // another thread got here first
+            return true;
+        }
+        live.failed.push(name);
+        if let Some(c) = providers::detect(&live.failed) {
+            event!(
+                warn,
+                "{name} broke ({why}), falling back to {}, as {}",
+                c.name,
+                c.why
+            );
+            live.chosen = c;
+            true
+        } else {
+            event!(
+                warn,
+                "{name} broke ({why}), and there's nothing to fall back to"
+            );
+            live.failed.pop();
+            false
+        }
+    }
+
+    /// The name of the backend, i.e. `"wl-copy"`. See [`Backend::name`].
+    /// Changes if the backend breaks and another is fallen back to.
+    #[must_use]
+    pub fn backend_name(&self) -> &'static str {
+        self.chosen().name
+    }
+
+    /// Why the backend was picked, i.e. `"WAYLAND_DISPLAY is set and wl-copy is installed"`.
+    #[must_use]
+    pub fn backend_reason(&self) -> &'static str {
+        self.chosen().why
+    }
+
+    /// The names of the backends that broke and were fallen back from, oldest first.
+    #[must_use]
+    pub fn failed_backends(&self) -> Vec<&'static str> {
+        self.live().failed.clone()
+    }
+
+    /// Copy text to the clipboard.
+    ///
+    /// # Errors
+    ///
+    /// If the clipboard backend fails.
+    pub fn copy(&self, text: &str) -> Result<()> {
+        self.copy_to(Selection::Clipboard, text)
+    }
+
+    /// Paste text from the clipboard.
+    ///
+    /// # Errors
+    ///
+    /// If the clipboard backend fails.
+    pub fn paste(&self) -> Result<String> {
+        self.paste_from(Selection::Clipboard)
+    }
+
+    /// Copy text to the clipboard, marked as a secret, so clipboard managers and history leave it out:
+    /// `x-kde-passwordManagerHint`, `org.nspasteboard.ConcealedType`, or windows'
+    /// `ExcludeClipboardContentFromMonitorProcessing`. Never goes in [`history`](crate::history) either.
+    ///
+    /// The marks need a backend that can hold several formats at once (the native ones, and the portal).
+    /// Others just copy the text.
+    ///
+    /// # Errors
+    ///
+    /// If the clipboard backend fails.
+    pub fn copy_sensitive(&self, text: &str) -> Result<()> {
+        self.sensitive(&endings(Op::Copy, text))
+    }
+
+    /// [`copy_sensitive`](Self::copy_sensitive), with the line endings already seen to
+    fn sensitive(&self, text: &str) -> Result<()> {
+        providers::fits(text.len())?;
+        let mut formats = vec![("text/plain", text.as_bytes())];
+        formats.extend_from_slice(CONCEALED);
+        match self.run(|p| p.copy_formats(Selection::Clipboard, &formats)) {
+            Err(ClipError::Unsupported) => self.run(|p| p.copy(Selection::Clipboard, text)),
+            r => r,
+        }
+    }
+
+    /// Copy text to the clipboard, kept out of the history or off other devices as `options` say.
+    ///
+    /// Like [`copy_sensitive`](Self::copy_sensitive), the marks need a backend that can hold several formats at once.
+    /// Others just copy the text.
+    ///
+    /// # Errors
+    ///
+    /// If the clipboard backend fails.
+    pub fn copy_with(&self, text: &str, options: CopyOptions) -> Result<()> {
+        let text = &*endings(Op::Copy, text);
+        providers::fits(text.len())?;
+        let mut formats = vec![("text/plain", text.as_bytes())];
+        if !options.history {
+            formats.extend_from_slice(NO_HISTORY);
+        }
+        if !options.cloud {
+            formats.extend_from_slice(NO_CLOUD);
+        }
+        match self.run(|p| p.copy_formats(Selection::Clipboard, &formats)) {
+            Err(ClipError::Unsupported) => self.run(|p| p.copy(Selection::Clipboard, text)),
+            r => r,
+        }?;
+        #[cfg(feature = "history")]
+        if options.history {
+            crate::history::record(text);
+        }
+        Ok(())
+    }
+
+    /// Copy a secret to the clipboard, [sensitively](Self::copy_sensitive).
+    /// clipp hands the backend the secret itself rather than a copy, and wipes anything it had to make
+    /// (the script for the kept powershell, see [`set_persistent`](crate::set_persistent)).
+    /// Its line endings are left as they are, whatever [`set_line_endings`](crate::set_line_endings) says,
+    /// as changing them would mean copies.
+    /// Backends that serve pastes from this process (the native x11 one, the portal, and memory)
+    /// keep the text until something else is copied.
+    ///
+    /// # Errors
+    ///
+    /// If the clipboard backend fails. The error never holds the secret.
+    #[cfg(feature = "secrecy")]
+    pub fn copy_secret(&self, secret: &secrecy::SecretString) -> Result<()> {
+        use secrecy::ExposeSecret;
+        self.sensitive(secret.expose_secret())
+    }
+
+    /// Copy text to the clipboard so it stays there after this program exits.
+    /// See [`copy_persistent`](crate::copy_persistent).
+    ///
+    /// # Errors
+    ///
+    /// If the backend serves the clipboard from this process and there's no tool to hand it to, or that fails.
+    pub fn copy_persistent(&self, text: &str) -> Result<()> {
+        let Some(p) = providers::handoff(self.backend_name())? else {
+            return self.copy(text);
+        };
+        let text = endings(Op::Copy, text);
+        p.copy(Selection::Clipboard, &text)?;
+        #[cfg(feature = "history")]
+        crate::history::record(&text);
+        Ok(())
+    }
+
+    /// Copy text to the clipboard, and clear it after `ttl`, unless something else has been copied since.
+    /// For passwords and such. The clearing happens on a background thread, so it doesn't happen if the program exits first.
+    ///
+    /// # Errors
+    ///
+    /// If the clipboard backend fails, or the thread can't be started.
+    pub fn copy_for(&self, text: &str, ttl: Duration) -> Result<()> {
+        self.copy(text)?;
+        // so the text isn't kept around. as the backend got it, and checked as it gives it back,
+        // so line endings and preferred formats don't hide it
+        let h = hash(&endings(Op::Copy, text));
+        let clip = self.clone();
+        std::thread::Builder::new()
+            .name("clipp ttl".into())
+            .spawn(move || {
+                std::thread::sleep(ttl);
+                match clip.run(|p| p.paste(Selection::Clipboard)) {
+                    Ok(now) if hash(&now) == h => _ = clip.clear(),
+                    // it can't tell, so clear to be safe
+                    Err(ClipError::Unsupported) => _ = clip.clear(),
+                    _ => {}
+                }
+            })
+            .map_err(ClipError::Io)?;
+        Ok(())
+    }
+
+    /// Paste, and copy back what `f` makes of it, back to back on the same backend.
+    /// `f` may be called again if the backend breaks partway, and another is fallen back to.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails, or what's there isn't text. Nothing is copied then.
+    pub fn transform(&self, mut f: impl FnMut(&str) -> String) -> Result<()> {
+        self.run(|p| {
+            let new = f(&endings_owned(Op::Paste, p.paste(Selection::Clipboard)?));
+            let new = endings(Op::Copy, &new);
+            p.copy(Selection::Clipboard, &new)?;
+            #[cfg(feature = "history")]
+            crate::history::record(&new);
+            Ok(())
+        })
+    }
+
+    /// Add `text` to the end of what's on the clipboard, with `sep` in between unless it's empty.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails, or what's there isn't text.
+    pub fn append(&self, text: &str, sep: &str) -> Result<()> {
+        self.transform(|old| match old {
+            "" => text.to_owned(),
+            old => format!("{old}{sep}{text}"),
+        })
+    }
+
+    /// Add `text` to the start of what's on the clipboard, with `sep` in between unless it's empty.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails, or what's there isn't text.
+    pub fn prepend(&self, text: &str, sep: &str) -> Result<()> {
+        self.transform(|old| match old {
+            "" => text.to_owned(),
+            old => format!("{text}{sep}{old}"),
+        })
+    }
+
+    /// Copy text to the clipboard until the returned guard is dropped, then put back what was there.
+    /// See [`scoped`](crate::scoped).
+    ///
+    /// # Errors
+    ///
+    /// If copying fails.
+    pub fn scoped(&self, text: &str) -> Result<ClipGuard> {
+        ClipGuard::new(self.clone(), text)
+    }
+
+    /// Keep the primary selection and the clipboard the same until the returned handle is dropped.
+    /// See [`sync_selections`](crate::sync_selections).
+    ///
+    /// # Errors
+    ///
+    /// If the backend doesn't have a primary selection, or the threads can't be started.
+    pub fn sync_selections(&self) -> Result<SelectionSync> {
+        SelectionSync::start(self)
+    }
+
+    /// Copy text to the clipboard, returning what was there before.
+    /// Both happen on the same backend, back to back.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails, or what was there isn't text. Nothing is copied then.
+    pub fn swap(&self, text: &str) -> Result<String> {
+        let text = endings(Op::Copy, text);
+        let old = self.run(|p| {
+            let old = endings_owned(Op::Paste, p.paste(Selection::Clipboard)?);
+            p.copy(Selection::Clipboard, &text)?;
+            Ok(old)
+        })?;
+        #[cfg(feature = "history")]
+        crate::history::record(&text);
+        Ok(old)
+    }
+
+    /// Paste, and parse it, ignoring whitespace around it.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails, or it doesn't parse.
+    pub fn paste_parse<T: FromStr>(&self) -> Result<T, ParseOrClipError<T::Err>> {
+        self.paste()?
+            .trim()
+            .parse()
+            .map_err(ParseOrClipError::Parse)
+    }
+
+    /// Copy `value` to the clipboard, as JSON.
+    ///
+    /// # Errors
+    ///
+    /// If it doesn't serialize, or the backend fails.
+    #[cfg(feature = "serde")]
+    pub fn copy_json<T: serde::Serialize + ?Sized>(
+        &self,
+        value: &T,
+    ) -> Result<(), ParseOrClipError<serde_json::Error>> {
+        let json = serde_json::to_string(value).map_err(ParseOrClipError::Parse)?;
+        Ok(self.copy(&json)?)
+    }
+
+    /// Paste JSON from the clipboard.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails, or it doesn't deserialize to `T`.
+    #[cfg(feature = "serde")]
+    pub fn paste_json<T: serde::de::DeserializeOwned>(
+        &self,
+    ) -> Result<T, ParseOrClipError<serde_json::Error>> {
+        serde_json::from_str(&self.paste()?).map_err(ParseOrClipError::Parse)
+    }
+
+    /// Copy text to the given selection.
+    ///
+    /// # Errors
+    ///
+    /// If the backend doesn't have this selection, or the backend fails.
+    pub fn copy_to(&self, sel: Selection, text: &str) -> Result<()> {
+        let text = endings(Op::Copy, text);
+        providers::fits(text.len())?;
+        self.run(|p| p.copy(sel, &text))?;
+        #[cfg(feature = "history")]
+        if sel == Selection::Clipboard {
+            crate::history::record(&text);
+        }
+        Ok(())
+    }
+
+    /// Paste text from the given selection.
+    ///
+    /// # Errors
+    ///
+    /// If the backend doesn't have this selection, or the backend fails.
+    pub fn paste_from(&self, sel: Selection) -> Result<String> {
+        if let Some(s) = self.paste_preferred(sel)? {
+            providers::fits(s.len())?;
+            return Ok(endings_owned(Op::Paste, s));
+        }
+        let s = self.run(|p| p.paste(sel))?;
+        // the commands already stopped, but the native backends have it all by now
+        providers::fits(s.len())?;
+        Ok(endings_owned(Op::Paste, s))
+    }
+
+    /// the first of [`set_preferred`](crate::set_preferred)'s mime types that's there, if it's text
+    fn paste_preferred(&self, sel: Selection) -> Result<Option<String>> {
+        let prefs = providers::preferred();
+        if prefs.is_empty() {
+            return Ok(None);
+        }
+        // can't tell what's there, so the text
+        let Ok(formats) = self.run(|p| p.formats(sel)) else {
+            return Ok(None);
+        };
+        let Some(mime) = providers::pick(&prefs, &formats) else {
+            return Ok(None);
+        };
+        let b = self.run(|p| p.paste_mime(sel, mime))?;
+        Ok(b.and_then(|b| String::from_utf8(b).ok()))
+    }
+
+    /// Clear the clipboard.
+    ///
+    /// # Errors
+    ///
+    /// If the clipboard backend fails.
+    pub fn clear(&self) -> Result<()> {
+        self.clear_selection(Selection::Clipboard)
+    }
+
+    /// Clear the given selection.
+    ///
+    /// # Errors
+    ///
+    /// If the backend doesn't have this selection, or the backend fails.
+    pub fn clear_selection(&self, sel: Selection) -> Result<()> {
+        self.run(|p| p.clear(sel))
+    }
+
+    /// Copy raw bytes to the clipboard.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    /// Text only backends (windows, klipper) will refuse bytes that aren't UTF-8.
+    pub fn copy_bytes(&self, bytes: &[u8]) -> Result<()> {
+        providers::fits(bytes.len())?;
+        self.run(|p| p.copy_bytes(Selection::Clipboard, bytes))?;
+        #[cfg(feature = "history")]
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            crate::history::record(text);
+        }
+        Ok(())
+    }
+
+    /// Paste raw bytes from the clipboard.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn paste_bytes(&self) -> Result<Vec<u8>> {
+        let b = self.run(|p| p.paste_bytes(Selection::Clipboard))?;
+        providers::fits(b.len())?;
+        Ok(b)
+    }
+
+    /// Paste text from the clipboard, with anything that isn't UTF-8 made into `U+FFFD`, rather than failing.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn paste_lossy(&self) -> Result<String> {
+        let bytes = self.paste_bytes()?;
+        Ok(endings_owned(
+            Op::Paste,
+            String::from_utf8_lossy(&bytes).into_owned(),
+        ))
+    }
+
+    /// Paste text from the clipboard, decoding it from whichever legacy encoding it looks like
+    /// (cp1252, shift-jis, gbk, and so on) if it isn't UTF-8, as old windows programs and x11 `STRING`s leave it.
+    /// The guess can be wrong for very short text.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    #[cfg(feature = "encoding")]
+    pub fn paste_decoded(&self) -> Result<String> {
+        let bytes = self.paste_bytes()?;
+        Ok(endings_owned(Op::Paste, crate::text::decode(bytes)))
+    }
+
+    /// Paste text from the clipboard as an [`OsString`], i.e. a file name that isn't UTF-8.
+    /// On unix, the bytes are kept as they are; elsewhere, what isn't UTF-8 is made into `U+FFFD`.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn paste_os_string(&self) -> Result<OsString> {
+        #[cfg(unix)]
+        return Ok(std::os::unix::ffi::OsStringExt::from_vec(
+            self.paste_bytes()?,
+        ));
+        #[cfg(not(unix))]
+        return self.paste_lossy().map(OsString::from);
+    }
+
+    /// Copy everything `r` reads to the clipboard, piping it to the backend's stdin as it's read,
+    /// so a big file never has to be in memory at once. Backends without a command read all of it first.
+    /// The [timeout](crate::set_timeout) still applies.
+    ///
+    /// ```no_run
+    /// let clip = clipp::Clipboard::new().unwrap();
+    /// clip.copy_from_reader(std::fs::File::open("big.log").unwrap()).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If reading fails, or the backend fails. Once something was read, it doesn't fall back to another backend,
+    /// as that can't be read again.
+    pub fn copy_from_reader(&self, mut r: impl Read) -> Result<()> {
+        self.run(|p| {
+            let mut r = Counted(&mut r, 0);
+            match p.copy_from_reader(Selection::Clipboard, &mut r) {
+                Err(e) if r.1 > 0 => Ok(Err(e)),
+                res => res.map(Ok),
+            }
+        })?
+    }
+
+    /// Paste raw bytes from the clipboard into `w`, as the backend prints them.
+    ///
+    /// ```no_run
+    /// let clip = clipp::Clipboard::new().unwrap();
+    /// clip.paste_to_writer(std::io::stdout().lock()).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If writing fails, or the backend fails. Once something was written, it doesn't fall back to another backend.
+    pub fn paste_to_writer(&self, mut w: impl Write) -> Result<()> {
+        self.run(|p| {
+            let mut w = Counted(&mut w, 0);
+            match p.paste_to_writer(Selection::Clipboard, &mut w) {
+                Err(e) if w.1 > 0 => Ok(Err(e)),
+                res => res.map(Ok),
+            }
+        })?
+    }
+
+    /// Paste text from the clipboard onto the end of `buf`, reusing its allocation.
+    /// `buf` is left as it was if this fails.
+    ///
+    /// # Errors
+    ///
+    /// If the clipboard backend fails, or the text isn't UTF-8.
+    pub fn paste_into(&self, buf: &mut String) -> Result<()> {
+        let mut v = std::mem::take(buf).into_bytes();
+        let start = v.len();
+        let r = self.paste_bytes_into(&mut v);
+        match String::from_utf8(v) {
+            Ok(mut s) => {
+                let tail = endings(Op::Paste, &s[start..]);
+                if let Cow::Owned(tail) = tail {
+                    s.truncate(start);
+                    s.push_str(&tail);
+                }
+                *buf = s;
+                r
+            }
+            Err(e) => {
+                let mut v = e.into_bytes();
+                let tail = v.split_off(start);
+                *buf = String::from_utf8(v).unwrap_or_default();
+                Err(ClipError::Utf8(String::from_utf8(tail).unwrap_err()))
+            }
+        }
+    }
+
+    /// Paste raw bytes from the clipboard onto the end of `buf`, reusing its allocation.
+    /// `buf` is left as it was if this fails.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn paste_bytes_into(&self, buf: &mut Vec<u8>) -> Result<()> {
+        let start = buf.len();
+        self.run(|p| {
+            buf.truncate(start);
+            p.paste_bytes_into(Selection::Clipboard, buf)
+        })
+        .and_then(|()| providers::fits(buf.len() - start))
+        .inspect_err(|_| buf.truncate(start))
+    }
+
+    /// Copy data of the given mime type (i.e. `text/html`) to the clipboard.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do mime types, or the backend fails.
+    pub fn copy_with_mime(&self, mime: &str, data: &[u8]) -> Result<()> {
+        providers::fits(data.len())?;
+        self.run(|p| p.copy_mime(Selection::Clipboard, mime, data))
+    }
+
+    /// Copy data of the given mime type that `render` makes when something first pastes it.
+    /// See [`copy_lazy`](crate::copy_lazy).
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do mime types, or the backend fails.
+    pub fn copy_lazy(
+        &self,
+        mime: &str,
+        render: impl FnOnce() -> Vec<u8> + Send + 'static,
+    ) -> Result<()> {
+        let data = Lazy::new(render);
+        match self.run(|p| p.copy_lazy(Selection::Clipboard, mime, data.clone())) {
+            // it has to be made now, then
+            Err(ClipError::Unsupported) => self.copy_with_mime(mime, &data.get()),
+            r => r,
+        }
+    }
+
+    /// Paste data of the given mime type from the clipboard.
+    /// Returns [`None`] if the clipboard doesn't hold that type.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do mime types, or the backend fails.
+    pub fn paste_mime(&self, mime: &str) -> Result<Option<Vec<u8>>> {
+        let b = self.run(|p| p.paste_mime(Selection::Clipboard, mime))?;
+        providers::fits(b.as_ref().map_or(0, Vec::len))?;
+        Ok(b)
+    }
+
+    /// How many bytes of text the clipboard holds, without pasting it, where the backend can tell:
+    /// x11 (without pasting it here, though the owner still sends it to the server), windows, and memory.
+    /// [`None`] elsewhere, or if the clipboard is empty. On windows, text is counted as it's kept there, in UTF-16.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn paste_len(&self) -> Result<Option<usize>> {
+        self.paste_mime_len("text/plain")
+    }
+
+    /// How many bytes of data of the given mime type the clipboard holds, without pasting it,
+    /// i.e. to show "clipboard: 48 MB image". See [`paste_len`](Self::paste_len).
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn paste_mime_len(&self, mime: &str) -> Result<Option<usize>> {
+        self.run(|p| p.size(Selection::Clipboard, mime))
+    }
+
+    /// The formats the clipboard holds, i.e. `["text/html", "text/plain"]`.
+    /// Mime types where there is one, and the platform's own names otherwise.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't list formats, or the backend fails.
+    pub fn available_formats(&self) -> Result<Vec<String>> {
+        self.run(|p| p.formats(Selection::Clipboard))
+    }
+
+    /// Copy a PNG image to the clipboard.
+    /// On windows, with the `image` feature, it's also put there as a `CF_DIBV5` bitmap, for paint and older programs.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do images, or the backend fails.
+    pub fn copy_image(&self, png: &[u8]) -> Result<()> {
+        self.copy_with_mime("image/png", png)
+    }
+
+    /// Paste a PNG image from the clipboard.
+    /// On windows, with the `image` feature, a bitmap (`CF_DIBV5` or `CF_DIB`) is converted if there's no PNG.
+    /// Returns [`None`] if the clipboard doesn't hold an image.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do images, or the backend fails.
+    pub fn paste_image(&self) -> Result<Option<Vec<u8>>> {
+        self.paste_mime("image/png")
+    }
+
+    /// Copy an image to the clipboard, as PNG. On windows it's also a bitmap, for programs older than PNG,
+    /// as with [`copy_image`](Self::copy_image).
+    ///
+    /// # Errors
+    ///
+    /// If it doesn't encode, the backend can't do images, or the backend fails.
+    #[cfg(feature = "image")]
+    pub fn copy_dynamic_image(
+        &self,
+        image: &image::DynamicImage,
+    ) -> Result<(), ParseOrClipError<image::ImageError>> {
+        Ok(self.copy_image(&encode(image, image::ImageFormat::Png)?)?)
+    }
+
+    /// Paste an image from the clipboard: PNG, or a bitmap if there's no PNG, i.e. from paint on windows.
+    /// Returns [`None`] if the clipboard doesn't hold an image.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do images, the backend fails, or the image doesn't decode.
+    #[cfg(feature = "image")]
+    pub fn paste_dynamic_image(
+        &self,
+    ) -> Result<Option<image::DynamicImage>, ParseOrClipError<image::ImageError>> {
+        use image::ImageFormat::{Bmp, Png};
+        for (mime, format) in [("image/png", Png), ("image/bmp", Bmp)] {
+            if let Some(b) = self.paste_mime(mime)? {
+                return image::load_from_memory_with_format(&b, format)
+                    .map(Some)
+                    .map_err(ParseOrClipError::Parse);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Copy html to the clipboard, along with a plain text version for things that can't take html.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't hold several formats at once, or the backend fails.
+    pub fn copy_html(&self, html: &str, plain: &str) -> Result<()> {
+        let plain = endings(Op::Copy, plain);
+        providers::fits(html.len() + plain.len())?;
+        self.run(|p| {
+            p.copy_formats(
+                Selection::Clipboard,
+                &[
+                    ("text/html", html.as_bytes()),
+                    ("text/plain", plain.as_bytes()),
+                ],
+            )
+        })?;
+        #[cfg(feature = "history")]
+        crate::history::record(&plain);
+        Ok(())
+    }
+
+    /// Paste html from the clipboard, with its plain text version, and the page it was copied from where the browser
+    /// says (windows' `SourceURL`, chromium's `chromium/x-source-url`). On windows, it's taken out of the
+    /// `HTML Format` header, and elsewhere out of the `<!--StartFragment-->` marks if it's in them.
+    /// Returns [`None`] if the clipboard doesn't hold html.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't paste formats, or the backend fails.
+    pub fn paste_html(&self) -> Result<Option<Html>> {
+        // with its header, which has the url in it
+        let raw = if cfg!(windows) {
+            self.paste_mime("HTML Format").ok().flatten()
+        } else {
+            None
+        };
+        let Some(data) = raw.map_or_else(|| self.paste_mime("text/html"), |r| Ok(Some(r)))? else {
+            return Ok(None);
+        };
+        let mut html = crate::html::parse(&data);
+        // the text itself, not what set_preferred would have paste give
+        html.plain = self
+            .run(|p| p.paste(Selection::Clipboard))
+            .ok()
+            .filter(|t| !t.is_empty())
+            .map(|t| endings_owned(Op::Paste, t));
+        html.source_url = html.source_url.or_else(|| {
+            let url = self.paste_mime("chromium/x-source-url").ok()??;
+            String::from_utf8(url).ok().filter(|u| !u.is_empty())
+        });
+        Ok(Some(html))
+    }
+
+    /// Copy markdown to the clipboard as rich text: rendered to html, with the markdown itself as the plain text.
+    /// "Copy as rich text", for pasting notes into mail and documents.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't hold several formats at once, or the backend fails.
+    #[cfg(feature = "markdown")]
+    pub fn copy_markdown(&self, md: &str) -> Result<()> {
+        use pulldown_cmark::{html, Options, Parser};
+        let mut rendered = String::new();
+        html::push_html(&mut rendered, Parser::new_ext(md, Options::all()));
+        self.copy_html(&rendered, md)
+    }
+
+    /// Copy rich text to the clipboard, along with a plain text version for things that can't take RTF.
+    /// For word processors and mail clients.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't hold several formats at once, or the backend fails.
+    pub fn copy_rtf(&self, rtf: &str, plain: &str) -> Result<()> {
+        let plain = endings(Op::Copy, plain);
+        providers::fits(rtf.len() + plain.len())?;
+        self.run(|p| {
+            p.copy_formats(
+                Selection::Clipboard,
+                &[
+                    ("text/rtf", rtf.as_bytes()),
+                    ("text/plain", plain.as_bytes()),
+                ],
+            )
+        })?;
+        #[cfg(feature = "history")]
+        crate::history::record(&plain);
+        Ok(())
+    }
+
+    /// Copy a list of files to the clipboard, for pasting into file managers.
+    /// Relative paths are made absolute.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do files, or the backend fails.
+    pub fn copy_files(&self, paths: &[PathBuf]) -> Result<()> {
+        self.run(|p| p.copy_files(Selection::Clipboard, paths))
+    }
+
+    /// Paste a list of files from the clipboard. Empty if there are none.
+    ///
+    /// Where it's a `text/uri-list`, the uris are decoded, with or without a `localhost`;
+    /// ones on other hosts are unc paths on windows, and left out elsewhere.
+    /// On windows and macos, explorer's and finder's file lists are read instead.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do files, or the backend fails.
+    pub fn paste_files(&self) -> Result<Vec<PathBuf>> {
+        self.run(|p| p.paste_files(Selection::Clipboard))
+    }
+
+    /// Copy a list of files to the clipboard, cut, so pasting them in a file manager moves them.
+    /// Relative paths are made absolute.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do files, or can't mark them cut (macos), or the backend fails.
+    pub fn cut_files(&self, paths: &[PathBuf]) -> Result<()> {
+        self.run(|p| p.copy_files_op(Selection::Clipboard, paths, FileOp::Cut))
+    }
+
+    /// Paste a list of files from the clipboard, and whether they were copied or cut.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't do files, or the backend fails.
+    pub fn paste_files_op(&self) -> Result<(Vec<PathBuf>, FileOp)> {
+        self.run(|p| p.paste_files_op(Selection::Clipboard))
+    }
+
+    /// Copy several things to the clipboard at once, each in several formats, by mime type.
+    /// On macos each is its own pasteboard item, as when several files or snippets are copied in finder;
+    /// elsewhere there is only room for one.
+    ///
+    /// ```no_run
+    /// let clip = clipp::Clipboard::new().unwrap();
+    /// clip.copy_items(&[
+    ///     &[("text/plain", b"one"), ("text/html", b"<b>one</b>")],
+    ///     &[("text/plain", b"two")],
+    /// ])
+    /// .unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't hold that many things or formats, or the backend fails.
+    pub fn copy_items(&self, items: &[&[(&str, &[u8])]]) -> Result<()> {
+        self.run(|p| p.copy_items(Selection::Clipboard, items))
+    }
+
+    /// Everything on the clipboard, each thing in every format it's in.
+    /// Several on macos, if several were copied; elsewhere at most one.
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't list formats, or the backend fails.
+    pub fn paste_items(&self) -> Result<Vec<Item>> {
+        self.run(|p| p.paste_items(Selection::Clipboard))
+    }
+
+    /// Everything on the clipboard, in every format, to put back later. See [`snapshot`](crate::snapshot).
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't list formats, or the backend fails.
+    pub fn snapshot(&self) -> Result<ClipboardSnapshot> {
+        self.paste_items().map(|items| ClipboardSnapshot { items })
+    }
+
+    /// Write everything on the clipboard, in every format, to `path`. See [`save`](crate::save).
+    ///
+    /// # Errors
+    ///
+    /// If the backend can't list formats, the backend fails, or `path` can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let saved = crate::saved::encode(&self.paste_items()?)?;
+        std::fs::write(path, saved).map_err(ClipError::Io)
+    }
+
+    /// Put back what [`save`](Self::save) wrote to `path`. See [`restore`](crate::restore).
+    ///
+    /// # Errors
+    ///
+    /// If `path` can't be read, or wasn't written by [`save`](Self::save), or the backend fails.
+    pub fn restore(&self, path: impl AsRef<Path>) -> Result<()> {
+        let b = std::fs::read(path).map_err(ClipError::Io)?;
+        let items = crate::saved::decode(&b)?;
+        ClipboardSnapshot { items }.restore_to(self)
+    }
+
+    /// Where the clipboard is at, to ask [`has_changed_since`](Self::has_changed_since) later.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn change_token(&self) -> Result<ChangeToken> {
+        self.run(|p| p.change_count(Selection::Clipboard))
+            .map(ChangeToken)
+    }
+
+    /// Whether the clipboard changed since `token` was taken.
+    /// Cheap on windows and macos, where the platform counts changes; elsewhere this pastes.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn has_changed_since(&self, token: ChangeToken) -> Result<bool> {
+        Ok(self.change_token()? != token)
+    }
+
+    /// A hash of what's on the clipboard, to compare with the last one instead of keeping the last text around.
+    /// The same for the same bytes, in the same program; it's not meant to be stored.
+    /// [`change_token`](Self::change_token) is cheaper where the platform counts changes, but also moves when the same text is copied again.
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn paste_hash(&self) -> Result<u64> {
+        self.paste_bytes().map(|b| hash(&b))
+    }
+
+    /// Call `f` from a background thread whenever the clipboard changes, until the returned handle is dropped.
+    /// See [`on_change`](crate::on_change).
+    ///
+    /// # Errors
+    ///
+    /// If the thread can't be started.
+    pub fn on_change(
+        &self,
+        f: impl FnMut(ClipboardEvent) + Send + 'static,
+    ) -> Result<Subscription> {
+        Subscription::start(self.watch(), f)
+    }
+
+    /// Watch the clipboard for changes, yielding the new text each time it changes.
+    /// See [`watch`](crate::watch).
+    #[must_use]
+    pub fn watch(&self) -> Watch {
+        self.watch_from(Selection::Clipboard)
+    }
+
+    /// Watch the given selection for changes.
+    #[must_use]
+    pub fn watch_from(&self, sel: Selection) -> Watch {
+        Watch::new(Some(self.shared()), sel)
+    }
+}
+
+/// a reader or writer, and how much went through it
+struct Counted<T>(T, usize);
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.0.read(buf)?;
+        self.1 += n;
+        Ok(n)
+    }
+}
+
+impl<W: Write> Write for Counted<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.0.write(buf)?;
+        self.1 += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(feature = "image")]
+fn encode(
+    image: &image::DynamicImage,
+    format: image::ImageFormat,
+) -> Result<Vec<u8>, ParseOrClipError<image::ImageError>> {
+    let mut b = std::io::Cursor::new(vec![]);
+    image
+        .write_to(&mut b, format)
+        .map_err(ParseOrClipError::Parse)?;
+    Ok(b.into_inner())
+}
+
+#[test]
+fn fall_back() {
+    struct Gone;
+    impl Provider for Gone {
+        fn clear(&self, _: Selection) -> Result<()> {
+            Err(ClipError::Spawn(std::io::ErrorKind::NotFound.into()))
+        }
+    }
+
+    let clip = Clipboard::of(Chosen {
+        clip: Arc::new(Gone),
+        name: "gone",
+        why: "testing",
+        falls_back: true,
+    });
+    match clip.clear() {
+        Ok(()) => {
+            assert_ne!(clip.backend_name(), "gone");
+            assert_eq!(clip.failed_backends(), ["gone"]);
+        }
+        // nothing else here
+        Err(e) => {
+            assert!(matches!(e, ClipError::Spawn(_)));
+            assert!(clip.failed_backends().is_empty());
+        }
+    }
+    // these don't
+    assert!(matches!(
+        Clipboard::with_provider(Gone).clear(),
+        Err(ClipError::Spawn(_))
+    ));
+}
+
+/// `f` comes true within a few seconds, for what background threads do
+#[cfg(test)]
+fn eventually(f: impl Fn() -> bool) -> bool {
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while !f() {
+        if std::time::Instant::now() > deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    true
+}
+
+#[test]
+fn copy_for() {
+    let clip = Clipboard::with_provider(providers::Memory::default());
+    clip.copy_for("secret", Duration::from_millis(10)).unwrap();
+    assert_eq!(clip.paste().unwrap(), "secret");
+    assert!(eventually(|| clip.paste().unwrap().is_empty()));
+    clip.copy_for("secret", Duration::from_millis(10)).unwrap();
+    clip.copy("mine").unwrap();
+    // nothing to wait on for it not happening; a slow thread only makes this pass for nothing
+    std::thread::sleep(Duration::from_millis(200));
+    assert_eq!(clip.paste().unwrap(), "mine");
+}
+
+#[test]
+fn copy_for_endings() {
+    // line endings are set for the whole process, so this gets one of its own
+    if std::env::var_os("CLIPP_TEST_ENDINGS").is_none() {
+        let ran = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["clipboard::copy_for_endings", "--exact", "--quiet"])
+            .env("CLIPP_TEST_ENDINGS", "1")
+            .status()
+            .unwrap();
+        assert!(ran.success());
+        return;
+    }
+    crate::set_line_endings(Op::Copy, Some(crate::LineEnding::CrLf));
+    let clip = Clipboard::with_provider(providers::Memory::default());
+    clip.copy_for("pass\nword", Duration::from_millis(10))
+        .unwrap();
+    assert_eq!(clip.paste().unwrap(), "pass\r\nword");
+    assert!(eventually(|| clip.paste_bytes().unwrap().is_empty()));
+}
+
+#[test]
+fn sync_selections() {
+    let clip = Clipboard::with_provider(providers::Memory::default());
+    let sync = clip.sync_selections().unwrap();
+    clip.copy_to(Selection::Primary, "selected").unwrap();
+    assert!(eventually(|| clip.paste().unwrap() == "selected"));
+    clip.copy("copied").unwrap();
+    assert!(eventually(
+        || clip.paste_from(Selection::Primary).unwrap() == "copied"
+    ));
+    drop(sync);
+}
+
+#[test]
+fn on_change() {
+    let clip = Clipboard::with_provider(providers::Memory::default());
+    let (tx, rx) = std::sync::mpsc::channel();
+    let sub = clip.on_change(move |e| _ = tx.send(e)).unwrap();
+    clip.copy("changed").unwrap();
+    assert_eq!(
+        rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+        ClipboardEvent {
+            selection: Selection::Clipboard,
+            text: "changed".into()
+        }
+    );
+    drop(sub);
+    // the thread's over, and the callback with it, without waiting for another change
+    assert_eq!(
+        rx.try_recv(),
+        Err(std::sync::mpsc::TryRecvError::Disconnected)
+    );
+}
+
+#[test]
+fn sensitive() {
+    let clip = Clipboard::with_provider(providers::Memory::default());
+    clip.copy_sensitive("hunter2").unwrap();
+    assert_eq!(clip.paste().unwrap(), "hunter2");
+    for (mime, data) in CONCEALED {
+        assert_eq!(clip.paste_mime(mime).unwrap().as_deref(), Some(*data));
+    }
+}
+
+#[test]
+fn copy_with() {
+    let clip = Clipboard::with_provider(providers::Memory::default());
+    let options = CopyOptions {
+        history: false,
+        ..Default::default()
+    };
+    clip.copy_with("not for win+v", options).unwrap();
+    assert_eq!(clip.paste().unwrap(), "not for win+v");
+    for (mime, data) in NO_HISTORY {
+        assert_eq!(clip.paste_mime(mime).unwrap().as_deref(), Some(*data));
+    }
+    clip.copy_with("anywhere", CopyOptions::default()).unwrap();
+    assert_eq!(clip.available_formats().unwrap(), ["text/plain"]);
+}
+
+#[test]
+fn lossy() {
+    let clip = Clipboard::with_provider(providers::Memory::default());
+    clip.copy_bytes(b"caf\xe9").unwrap();
+    assert!(matches!(clip.paste(), Err(ClipError::Utf8(_))));
+    assert_eq!(clip.paste_lossy().unwrap(), "caf\u{fffd}");
+    #[cfg(unix)]
+    assert_eq!(
+        std::os::unix::ffi::OsStrExt::as_bytes(&*clip.paste_os_string().unwrap()),
+        b"caf\xe9"
+    );
+}
+
+#[test]
+fn streams() {
+    let clip = Clipboard::with_provider(providers::Memory::default());
+    clip.copy_from_reader(&b"read in"[..]).unwrap();
+    let mut out = vec![];
+    clip.paste_to_writer(&mut out).unwrap();
+    assert_eq!(out, b"read in");
+}
+
+#[test]
+fn paste_into() {
+    crate::testing::with_mock(|mock| {
+        let mut s = String::from("a");
+        mock.set_text("b");
+        crate::paste_into(&mut s);
+        assert_eq!(s, "ab");
+        mock.set_data("text/plain", b"\xff");
+        crate::try_paste_into(&mut s).unwrap_err();
+        assert_eq!(s, "ab");
+        let mut v = vec![b'c'];
+        mock.set_text("d");
+        crate::paste_bytes_into(&mut v);
+        assert_eq!(v, b"cd");
+    });
+}
+
+#[test]
+fn swap() {
+    crate::testing::with_mock(|mock| {
+        mock.set_text("old");
+        assert_eq!(crate::swap("new"), "old");
+        assert_eq!(mock.text().as_deref(), Some("new"));
+        mock.set_data("text/plain", b"\xff");
+        crate::try_swap("newer").unwrap_err();
+        assert_eq!(mock.data("text/plain").as_deref(), Some(&b"\xff"[..]));
+    });
+}
+
+#[test]
+fn append() {
+    crate::testing::with_mock(|mock| {
+        crate::append("a", "\n");
+        crate::append("b", "\n");
+        crate::prepend("c", " ");
+        assert_eq!(mock.text().as_deref(), Some("c a\nb"));
+    });
+}
+
+#[test]
+fn transform() {
+    crate::testing::with_mock(|mock| {
+        mock.set_text(" hi ");
+        crate::transform(|s| s.trim().to_uppercase());
+        assert_eq!(mock.text().as_deref(), Some("HI"));
+        mock.set_data("text/plain", b"\xff");
+        crate::try_transform(str::to_owned).unwrap_err();
+    });
+}
+
+#[test]
+fn paste_parse() {
+    crate::testing::with_mock(|mock| {
+        mock.set_text(" 127.0.0.1\n");
+        let ip: std::net::Ipv4Addr = crate::paste_parse().unwrap();
+        assert!(ip.is_loopback());
+        assert!(matches!(
+            crate::paste_parse::<u8>(),
+            Err(crate::ParseOrClipError::Parse(_))
+        ));
+    });
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json() {
+    crate::testing::with_mock(|mock| {
+        crate::copy_json(&("a", 1)).unwrap();
+        assert_eq!(mock.text().as_deref(), Some(r#"["a",1]"#));
+        assert_eq!(
+            crate::paste_json::<(String, u8)>().unwrap(),
+            ("a".into(), 1)
+        );
+        crate::paste_json::<bool>().unwrap_err();
+    });
+}
+
+#[test]
+fn change_token() {
+    crate::testing::with_mock(|mock| {
+        let t = crate::change_token();
+        assert!(!crate::has_changed_since(t));
+        mock.set_text("elsewhere");
+        assert!(crate::has_changed_since(t));
+        let t = crate::change_token();
+        mock.clear();
+        assert!(crate::has_changed_since(t));
+    });
+}
+
+#[test]
+fn selections() {
+    crate::testing::with_mock(|mock| {
+        crate::copy_to(Selection::Primary, "selected");
+        crate::copy("copied");
+        assert_eq!(crate::paste_from(Selection::Primary), "selected");
+        assert_eq!(mock.text().as_deref(), Some("copied"));
+        crate::clear_selection(Selection::Primary);
+        assert_eq!(crate::paste_from(Selection::Primary), "");
+        assert_eq!(crate::paste_from(Selection::Clipboard), "copied");
+    });
+}
+
+#[test]
+fn copy_persistent() {
+    crate::testing::with_mock(|mock| {
+        crate::copy_persistent("stays");
+        assert_eq!(mock.text().as_deref(), Some("stays"));
+    });
+}
+
+#[test]
+fn copy_lazy() {
+    crate::testing::with_mock(|mock| {
+        let made = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let m = Arc::clone(&made);
+        crate::copy_lazy("text/csv", move || {
+            m.store(true, std::sync::atomic::Ordering::Relaxed);
+            b"a,b".to_vec()
+        });
+        assert!(!made.load(std::sync::atomic::Ordering::Relaxed));
+        assert_eq!(mock.data("text/csv").as_deref(), Some(&b"a,b"[..]));
+        assert!(made.load(std::sync::atomic::Ordering::Relaxed));
+    });
+}
+
+#[test]
+fn rich() {
+    crate::testing::with_mock(|mock| {
+        crate::copy_rtf(r"{\rtf1 {\b hi}}", "hi");
+        assert_eq!(
+            mock.data("text/rtf").as_deref(),
+            Some(&br"{\rtf1 {\b hi}}"[..])
+        );
+        assert_eq!(crate::paste(), "hi");
+    });
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn markdown() {
+    crate::testing::with_mock(|mock| {
+        crate::copy_markdown("**hi**");
+        assert_eq!(
+            mock.data("text/html").as_deref(),
+            Some(&b"<p><strong>hi</strong></p>\n"[..])
+        );
+        assert_eq!(crate::paste(), "**hi**");
+    });
+}
+
+#[test]
+fn cut_files() {
+    crate::testing::with_mock(|mock| {
+        let files = vec![std::path::PathBuf::from("/tmp/moved")];
+        crate::cut_files(&files);
+        assert_eq!(
+            mock.data("application/x-kde-cutselection").as_deref(),
+            Some(&b"1"[..])
+        );
+        assert_eq!(crate::paste_files_op(), (files.clone(), crate::FileOp::Cut));
+        crate::copy_files(&files);
+        assert_eq!(crate::paste_files_op(), (files, crate::FileOp::Copy));
+    });
+}
+
+#[test]
+fn items() {
+    crate::testing::with_mock(|_| {
+        crate::copy_items(&[&[("text/html", b"<b>hi</b>"), ("text/plain", b"hi")]]);
+        assert_eq!(
+            crate::paste_items(),
+            [vec![
+                ("text/html".to_owned(), b"<b>hi</b>".to_vec()),
+                ("text/plain".to_owned(), b"hi".to_vec())
+            ]]
+        );
+        assert!(
+            crate::try_copy_items(&[&[("text/plain", b"1")], &[("text/plain", b"2")]]).is_err()
+        );
+        crate::copy_items(&[]);
+        assert!(crate::paste_items().is_empty());
+    });
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn dynamic_image() {
+    crate::testing::with_mock(|mock| {
+        let img = image::DynamicImage::new_rgba8(2, 3);
+        crate::copy_dynamic_image(&img).unwrap();
+        assert!(mock.data("image/png").is_some());
+        assert_eq!(crate::paste_dynamic_image().unwrap(), Some(img));
+        mock.set_text("no image");
+        assert_eq!(crate::paste_dynamic_image().unwrap(), None);
+    });
+}
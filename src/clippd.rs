@@ -0,0 +1,245 @@
+//! The clipboard daemon. Opt in with the `clippd` feature, which builds the `clippd` binary,
+//! and has clipp pick it whenever it's running. Only on unix.
+//!
+//! It keeps what's copied on disk, so the [`history`] outlives reboots, and puts the clipboard back
+//! when the program that copied exits and takes it along, as happens on x11 and wayland.
+//! What's copied through it is served from it, so it stays after the program that copied exits.
+//! Text marked as a password (see [`copy_sensitive`](crate::copy_sensitive)) is neither kept nor put back.
+//! With [`set_restore`] (`clippd --restore`), the clipboard itself outlives reboots too.
+//!
+//! It listens on `$XDG_RUNTIME_DIR/clippd.sock`, which only its user can use.
+//!
+//! ```no_run
+//! // what clippd does, in a program of your own
+//! clipp::clippd::serve().unwrap();
+//! ```
+use crate::{
+    providers::clippd::{
+        join, read_frame, socket, split, split_bytes, write_frame, Ask, Clippd, ABSENT, FAILED, OK,
+        SERVING, UNSUPPORTED,
+    },
+    ClipError, Clipboard, Result, Selection,
+};
+use rustix::{fs::Mode, process::umask};
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, Read, Write},
+    os::unix::{
+        fs::{OpenOptionsExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+    time::Duration,
+};
+
+/// what the clipboard last held, to put back. [`None`] once it's cleared on purpose
+static KEPT: Mutex<Option<String>> = Mutex::new(None);
+
+fn kept() -> std::sync::MutexGuard<'static, Option<String>> {
+    KEPT.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Whether clippd is running, so clipp uses it.
+#[must_use]
+pub fn running() -> bool {
+    Clippd::running().is_some()
+}
+
+/// What clippd has seen copied, newest first, since long before this program started.
+///
+/// # Errors
+///
+/// If it isn't running ([`ClipError::NoBackend`]), or doesn't answer.
+pub fn history() -> Result<Vec<String>> {
+    let c = Clippd::running().ok_or(ClipError::NoBackend)?;
+    split(
+        &c.ask(Ask::History, Selection::Clipboard, "", &[])?
+            .unwrap_or_default(),
+    )
+}
+
+static RESTORE: AtomicBool = AtomicBool::new(false);
+
+/// Save the clipboard, in every format, whenever it changes, and put it back when clippd starts,
+/// i.e. after a reboot. Off by default. See [`save`](crate::save).
+pub fn set_restore(on: bool) {
+    RESTORE.store(on, Ordering::Relaxed);
+}
+
+/// `name` in `$XDG_DATA_HOME/clipp`, or `~/.local/share/clipp`, which is made if it isn't there
+fn data(name: &str) -> Option<PathBuf> {
+    let d = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))?
+        .join("clipp");
+    _ = std::fs::create_dir_all(&d);
+    Some(d.join(name))
+}
+
+/// write `b` to `f`, only for its user, as it's what they copied. made that way, so it's never anyone else's
+fn private(f: &Path, b: &[u8]) -> std::io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(f)?;
+    // from before it was made so
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(b)
+}
+
+fn save(clip: &Clipboard) {
+    let Some(f) = data("history") else { return };
+    _ = private(&f, &join(&crate::history::entries()));
+    if RESTORE.load(Ordering::Relaxed) {
+        let Some(f) = data("clipboard") else { return };
+        if let Ok(b) = clip.paste_items().and_then(|i| crate::saved::encode(&i)) {
+            _ = private(&f, &b);
+        }
+    }
+}
+
+/// Be clippd: answer other programs, and look after the clipboard, for ever.
+///
+/// # Errors
+///
+/// If clippd is already running, no clipboard is available, or the socket can't be made.
+pub fn serve() -> Result<()> {
+    if running() {
+        return Err(ClipError::Io(std::io::Error::new(
+            ErrorKind::AddrInUse,
+            "clippd is already running",
+        )));
+    }
+    SERVING.store(true, Ordering::Relaxed);
+    let clip = crate::current()?;
+    if let Some(b) = data("history").and_then(|f| std::fs::read(f).ok()) {
+        // recorded oldest first, so the newest ends up in front
+        for text in split(&b).unwrap_or_default().iter().rev() {
+            crate::history::record(text);
+        }
+    }
+    if RESTORE.load(Ordering::Relaxed) && clip.paste().map_or(true, |t| t.is_empty()) {
+        if let Some(f) = data("clipboard").filter(|f| f.exists()) {
+            _ = clip.restore(f);
+        }
+    }
+    let path = socket();
+    // left by one that was killed
+    _ = std::fs::remove_file(&path);
+    // so it's only ever its user's, from the start
+    let was = umask(Mode::from_raw_mode(0o177));
+    let listener = UnixListener::bind(&path);
+    umask(was);
+    let listener = listener.map_err(ClipError::Io)?;
+    event!(
+        info,
+        "clippd listening on {}, with {}",
+        path.display(),
+        clip.backend_name()
+    );
+    let keeper = clip.clone();
+    std::thread::Builder::new()
+        .name("clippd keep".into())
+        .spawn(move || keep(&keeper))
+        .map_err(ClipError::Io)?;
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let clip = clip.clone();
+        _ = std::thread::Builder::new()
+            .name("clippd answer".into())
+            .spawn(move || answer(&clip, stream));
+    }
+    Ok(())
+}
+
+/// record what's copied, and put the last of it back when it's gone
+fn keep(clip: &Clipboard) {
+    let concealed = |clip: &Clipboard| {
+        clip.run(|p| p.formats(Selection::Clipboard))
+            .is_ok_and(|f| f.iter().any(|m| m == "x-kde-passwordManagerHint"))
+    };
+    // nobody owns it, rather than it holding something that isn't text, or pasting failing for now
+    let gone = |clip: &Clipboard| {
+        clip.run(|p| p.formats(Selection::Clipboard))
+            .is_ok_and(|f| f.is_empty())
+    };
+    loop {
+        std::thread::sleep(Duration::from_millis(250));
+        match clip.paste() {
+            Ok(text) if kept().as_deref() == Some(&*text) => {}
+            // so it isn't put back once the password manager clears it
+            Ok(_) if concealed(clip) => *kept() = None,
+            Ok(text) if !text.is_empty() => {
+                crate::history::record(&text);
+                save(clip);
+                *kept() = Some(text);
+            }
+            // the program that copied exited
+            _ if gone(clip) => {
+                let Some(text) = kept().clone() else { continue };
+                event!(debug, "clippd putting back what was copied");
+                _ = clip.copy(&text);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn answer(clip: &Clipboard, mut s: UnixStream) {
+    let (status, body) = match respond(clip, &mut s) {
+        Ok(Some(b)) => (OK, b),
+        Ok(None) => (ABSENT, vec![]),
+        Err(ClipError::Unsupported) => (UNSUPPORTED, vec![]),
+        Err(e) => (FAILED, e.to_string().into_bytes()),
+    };
+    _ = s
+        .write_all(&[status])
+        .and_then(|()| write_frame(&mut s, &body));
+}
+
+/// do what was asked, with the clipboard it found
+fn respond(clip: &Clipboard, s: &mut UnixStream) -> Result<Option<Vec<u8>>> {
+    let mut head = [0; 2];
+    s.read_exact(&mut head).map_err(ClipError::Io)?;
+    let ask = Ask::from_byte(head[0]).ok_or(ClipError::Unsupported)?;
+    let sel = match head[1] {
+        b'p' => Selection::Primary,
+        _ => Selection::Clipboard,
+    };
+    let mime = String::from_utf8(read_frame(s)?).map_err(ClipError::Utf8)?;
+    let data = read_frame(s)?;
+    let done = |()| Some(vec![]);
+    match ask {
+        Ask::Copy if mime.is_empty() => clip.run(|p| p.copy_bytes(sel, &data)).map(done),
+        Ask::Copy => clip.run(|p| p.copy_mime(sel, &mime, &data)).map(done),
+        Ask::CopyFormats => {
+            let all = split_bytes(&data)?;
+            let formats = all
+                .chunks_exact(2)
+                .map(|f| {
+                    Ok((
+                        std::str::from_utf8(&f[0]).map_err(|_| ClipError::Unsupported)?,
+                        &*f[1],
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            clip.run(|p| p.copy_formats(sel, &formats)).map(done)
+        }
+        Ask::Paste if mime.is_empty() => clip.run(|p| p.paste_bytes(sel)).map(Some),
+        Ask::Paste => clip.run(|p| p.paste_mime(sel, &mime)),
+        Ask::Clear => {
+            if sel == Selection::Clipboard {
+                *kept() = None;
+            }
+            clip.run(|p| p.clear(sel)).map(done)
+        }
+        Ask::Formats => clip.run(|p| p.formats(sel)).map(|f| Some(join(&f))),
+        Ask::History => Ok(Some(join(&crate::history::entries()))),
+    }
+}
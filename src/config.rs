@@ -0,0 +1,212 @@
+//! Settings from a file, so the people using a program built on clipp can fix what it picks
+//! without the program growing flags for it. Opt in with the `config` feature.
+//!
+//! The file is `clipp/config.toml` in the config directory: `$XDG_CONFIG_HOME`, `~/.config`,
+//! or `%APPDATA%` on windows. `CLIPP_CONFIG` names another. It's read when the clipboard is first used,
+//! and what it says beats what the program set, but not the `CLIPP_*` environment variables.
+//! Everything is optional:
+//!
+//! ```toml
+//! # like CLIPP_BACKEND, CLIPP_FALLBACK, and CLIPP_PRIORITY
+//! backend = "xclip"
+//! fallback = "memory"
+//! priority = ["xclip", "klipper"]
+//! # like clipp::set_persistent and clipp::set_seat
+//! persistent = true
+//! seat = "seat1"
+//! # like clipp::set_preferred, and CLIPP_PREFER
+//! prefer = ["text/markdown", "text/html"]
+//!
+//! # in milliseconds, or false to wait forever
+//! [timeout]
+//! copy = 5000
+//! paste = false
+//!
+//! # "lf", "crlf", or "keep"
+//! [line-endings]
+//! paste = "lf"
+//!
+//! # a clipboard of your own, as with clipp::Commands. beats backend
+//! [commands]
+//! copy = "my-copy --stdin"
+//! paste = "my-paste"
+//! ```
+use crate::{
+    providers::{self, Chosen},
+    Backend, Commands, LineEnding, Op,
+};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use toml::{Table, Value};
+
+/// What the file said.
+#[derive(Debug, Default)]
+// left unset, and set to forever (or to leave them be), are different
+#[allow(clippy::option_option)]
+pub(crate) struct Config {
+    backend: Option<Backend>,
+    fallback: Option<Backend>,
+    priority: Vec<Backend>,
+    persistent: Option<bool>,
+    seat: Option<String>,
+    prefer: Vec<String>,
+    /// by [`Op`]. [`None`] inside is forever
+    timeout: [Option<Option<Duration>>; 2],
+    /// by [`Op`]. [`None`] inside leaves them be
+    endings: [Option<Option<LineEnding>>; 2],
+    commands: Option<Commands>,
+}
+
+impl Config {
+    /// what's wrong is left out
+    fn parse(s: &str) -> Self {
+        let Ok(t) = s.parse::<Table>() else {
+            return Self::default();
+        };
+        let backend = |k| t.get(k)?.as_str()?.parse().ok();
+        Self {
+            backend: backend("backend"),
+            fallback: backend("fallback"),
+            priority: t
+                .get("priority")
+                .and_then(Value::as_array)
+                .map(|p| p.iter().filter_map(|b| b.as_str()?.parse().ok()).collect())
+                .unwrap_or_default(),
+            persistent: t.get("persistent").and_then(Value::as_bool),
+            seat: t.get("seat").and_then(Value::as_str).map(str::to_owned),
+            prefer: t
+                .get("prefer")
+                .and_then(Value::as_array)
+                .map(|p| {
+                    p.iter()
+                        .filter_map(|m| Some(m.as_str()?.to_owned()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            timeout: by_op(&t, "timeout", |v| match v {
+                Value::Boolean(false) => Some(None),
+                Value::Integer(ms) => Some(Some(Duration::from_millis(u64::try_from(*ms).ok()?))),
+                _ => None,
+            }),
+            endings: by_op(&t, "line-endings", |v| {
+                match &*v.as_str()?.to_ascii_lowercase() {
+                    "lf" => Some(Some(LineEnding::Lf)),
+                    "crlf" => Some(Some(LineEnding::CrLf)),
+                    "keep" => Some(None),
+                    _ => None,
+                }
+            }),
+            commands: t.get("commands").and_then(Value::as_table).and_then(|c| {
+                let get = |k| c.get(k)?.as_str();
+                Some(Commands::new(get("copy")?, get("paste")?))
+            }),
+        }
+    }
+
+    /// the backend it names, before detecting one
+    pub(crate) fn chosen(&self) -> Option<Chosen> {
+        if let Some(c) = &self.commands {
+            return Some(Chosen {
+                clip: Arc::new(c.clone()),
+                name: "custom",
+                why: "the config file gives commands",
+                falls_back: false,
+            });
+        }
+        Chosen::new(self.backend?, "the config file says so")
+    }
+
+    /// the backend it names for when none is found
+    pub(crate) fn fallback(&self) -> Option<Chosen> {
+        Chosen::new(
+            self.fallback?,
+            "nothing was found and the config file names a fallback",
+        )
+    }
+}
+
+/// `[k]`'s `copy` and `paste`, by [`Op`]
+fn by_op<T>(t: &Table, k: &str, f: impl Fn(&Value) -> Option<T>) -> [Option<T>; 2] {
+    let t = t.get(k).and_then(Value::as_table);
+    ["copy", "paste"].map(|op| f(t?.get(op)?))
+}
+
+/// Where the config file is, or would be.
+#[must_use]
+pub fn path() -> Option<PathBuf> {
+    if let Some(p) = std::env::var_os("CLIPP_CONFIG") {
+        return Some(p.into());
+    }
+    #[cfg(target_family = "windows")]
+    let dir = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(target_family = "windows"))]
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")));
+    dir.map(|d| d.join("clipp").join("config.toml"))
+}
+
+fn load() -> Config {
+    path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .map(|s| Config::parse(&s))
+        .unwrap_or_default()
+}
+
+/// read the file, set what it sets, and say what it picked, if it did
+pub(crate) fn apply() -> Config {
+    let c = load();
+    for op in [Op::Copy, Op::Paste] {
+        if let Some(t) = c.timeout[op as usize] {
+            providers::set_timeout(op, t);
+        }
+        if let Some(e) = c.endings[op as usize] {
+            crate::set_line_endings(op, e);
+        }
+    }
+    if let Some(p) = c.persistent {
+        providers::set_persistent(p);
+    }
+    if c.seat.is_some() {
+        providers::set_seat(c.seat.as_deref());
+    }
+    if !c.priority.is_empty() && std::env::var_os("CLIPP_PRIORITY").is_none() {
+        providers::set_priority(&c.priority);
+    }
+    if !c.prefer.is_empty() && std::env::var_os("CLIPP_PREFER").is_none() {
+        let prefer: Vec<&str> = c.prefer.iter().map(|m| &**m).collect();
+        providers::set_preferred(&prefer);
+    }
+    c
+}
+
+#[test]
+fn parse() {
+    let c = Config::parse(
+        r#"
+        backend = "xclip"
+        priority = ["xsel", "nonsense", "klipper"]
+        persistent = true
+        prefer = ["text/html", 1]
+        [timeout]
+        copy = 100
+        paste = false
+        [line-endings]
+        paste = "CRLF"
+        [commands]
+        copy = "a b"
+        "#,
+    );
+    assert_eq!(c.backend, Some(Backend::XClip));
+    assert_eq!(c.fallback, None);
+    assert_eq!(c.priority, [Backend::XSel, Backend::Klipper]);
+    assert_eq!(c.persistent, Some(true));
+    assert_eq!(c.prefer, ["text/html"]);
+    assert_eq!(
+        c.timeout,
+        [Some(Some(Duration::from_millis(100))), Some(None)]
+    );
+    assert_eq!(c.endings, [None, Some(Some(LineEnding::CrLf))]);
+    // no paste command
+    assert!(c.commands.is_none());
+    assert!(Config::parse("not [toml").backend.is_none());
+}
@@ -0,0 +1,114 @@
+//! the error type
+use std::{fmt, io, process::ExitStatus, string::FromUtf8Error};
+
+/// Something went wrong talking to the clipboard.
+#[derive(Debug)]
+pub enum ClipError {
+    /// No clipboard provider could be found.
+    NoBackend,
+    /// The clipboard command could not be spawned.
+    Spawn(io::Error),
+    /// The clipboard command exited unsuccessfully.
+    Status {
+        /// How it exited.
+        status: ExitStatus,
+        /// What it said on stderr, trimmed.
+        stderr: String,
+    },
+    /// Reading from or writing to the clipboard failed.
+    Io(io::Error),
+    /// The clipboard backend doesn't support this operation.
+    Unsupported,
+    /// The clipboard contents were not valid UTF-8.
+    Utf8(FromUtf8Error),
+    /// A native clipboard backend failed.
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+    /// The clipboard was already in use, so it's too late to choose another.
+    AlreadyChosen,
+    /// The clipboard command took too long, and was killed. See [`set_timeout`](crate::set_timeout).
+    Timeout,
+    /// What was copied or pasted was bigger than the limit. See [`set_max_size`](crate::set_max_size).
+    TooBig {
+        /// The limit, in bytes.
+        limit: usize,
+    },
+}
+
+impl ClipError {
+    /// did the backend itself break, rather than refuse
+    pub(crate) fn broken(&self) -> bool {
+        matches!(
+            self,
+            Self::Spawn(_) | Self::Status { .. } | Self::Io(_) | Self::Backend(_) | Self::Timeout
+        )
+    }
+}
+
+impl fmt::Display for ClipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoBackend => f.write_str("no clipboard available"),
+            Self::Spawn(e) => write!(f, "failed to spawn clipboard command: {e}"),
+            Self::Status { status, stderr } if stderr.is_empty() => {
+                write!(f, "clipboard command failed: {status}")
+            }
+            Self::Status { status, stderr } => {
+                write!(f, "clipboard command failed: {status}: {stderr}")
+            }
+            Self::Io(e) => write!(f, "clipboard io failed: {e}"),
+            Self::Unsupported => f.write_str("unsupported by this clipboard"),
+            Self::Utf8(e) => write!(f, "clipboard text is not utf8: {e}"),
+            Self::Backend(e) => write!(f, "clipboard backend failed: {e}"),
+            Self::AlreadyChosen => f.write_str("a clipboard was already chosen"),
+            Self::Timeout => f.write_str("clipboard command timed out"),
+            Self::TooBig { limit } => write!(f, "clipboard content is over the {limit} byte limit"),
+        }
+    }
+}
+
+impl std::error::Error for ClipError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Spawn(e) | Self::Io(e) => Some(e),
+            Self::Utf8(e) => Some(e),
+            Self::Backend(e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+/// Pasting, or parsing what was pasted, went wrong. See [`paste_parse`](crate::paste_parse).
+#[derive(Debug)]
+pub enum ParseOrClipError<E> {
+    /// Pasting failed.
+    Clip(ClipError),
+    /// What was pasted didn't parse. For [`copy_json`](crate::copy_json), the value didn't serialize.
+    Parse(E),
+}
+
+impl<E> From<ClipError> for ParseOrClipError<E> {
+    fn from(e: ClipError) -> Self {
+        Self::Clip(e)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ParseOrClipError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clip(e) => e.fmt(f),
+            Self::Parse(e) => write!(f, "clipboard contents did not parse: {e}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for ParseOrClipError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Clip(e) => e.source(),
+            Self::Parse(e) => Some(e),
+        }
+    }
+}
+
+/// Result type for clipboard operations.
+pub type Result<T, E = ClipError> = std::result::Result<T, E>;
@@ -0,0 +1,76 @@
+//! a C interface, for everything that isn't rust. see `include/clipp.h`.
+//!
+//! build the library with `cargo rustc --release --lib --crate-type cdylib --features ffi`
+//! (or `staticlib`).
+#![allow(unsafe_code)]
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    ptr,
+};
+
+/// Copy `text`, a nul terminated UTF-8 string. `0` if it worked, `-1` if it didn't.
+///
+/// # Safety
+///
+/// `text` must be null, or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn clipp_copy(text: *const c_char) -> c_int {
+    if text.is_null() {
+        return -1;
+    }
+    // SAFETY: nul terminated, says the caller
+    let Ok(text) = unsafe { CStr::from_ptr(text) }.to_str() else {
+        return -1;
+    };
+    match crate::try_copy(text) {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Paste, as a nul terminated UTF-8 string to give back to [`clipp_free`].
+/// Null if it didn't work, or the text holds a nul.
+#[no_mangle]
+pub extern "C" fn clipp_paste() -> *mut c_char {
+    crate::try_paste()
+        .ok()
+        .and_then(|s| CString::new(s).ok())
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Empty the clipboard. `0` if it worked, `-1` if it didn't.
+#[no_mangle]
+pub extern "C" fn clipp_clear() -> c_int {
+    match crate::try_clear() {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Free a string from [`clipp_paste`]. Does nothing with null.
+///
+/// # Safety
+///
+/// `s` must be null, or from [`clipp_paste`] and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn clipp_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: from CString::into_raw, says the caller
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+#[test]
+fn ffi() {
+    crate::testing::with_mock(|mock| unsafe {
+        assert_eq!(clipp_copy(c"hi".as_ptr()), 0);
+        assert_eq!(mock.text().as_deref(), Some("hi"));
+        assert_eq!(clipp_copy(ptr::null()), -1);
+        let p = clipp_paste();
+        assert_eq!(CStr::from_ptr(p), c"hi");
+        clipp_free(p);
+        clipp_free(ptr::null_mut());
+        assert_eq!(clipp_clear(), 0);
+        assert_eq!(mock.text(), None);
+    });
+}
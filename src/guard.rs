@@ -0,0 +1,63 @@
+//! putting the clipboard back
+use crate::Clipboard;
+
+/// Puts back what was on the clipboard when it's dropped. See [`scoped`](crate::scoped).
+///
+/// Only text (and other bytes the backend pastes as text) is put back; images and such are lost.
+/// If what was there couldn't be pasted, the clipboard is left holding the new text.
+#[must_use = "the clipboard is put back as soon as this is dropped"]
+pub struct ClipGuard {
+    clip: Clipboard,
+    was: Was,
+}
+
+/// what was on the clipboard before
+enum Was {
+    Empty,
+    Bytes(Vec<u8>),
+    /// it couldn't be pasted, so there's no telling what to put back
+    Unknown,
+}
+
+impl ClipGuard {
+    pub(crate) fn new(clip: Clipboard, text: &str) -> crate::Result<Self> {
+        let was = match clip.paste_bytes() {
+            Ok(b) if b.is_empty() => Was::Empty,
+            Ok(b) => Was::Bytes(b),
+            Err(_) => Was::Unknown,
+        };
+        clip.copy(text)?;
+        Ok(Self { clip, was })
+    }
+}
+
+impl Drop for ClipGuard {
+    fn drop(&mut self) {
+        _ = match std::mem::replace(&mut self.was, Was::Unknown) {
+            Was::Empty => self.clip.clear(),
+            Was::Bytes(b) => self.clip.copy_bytes(&b),
+            // clearing it would lose whatever it was
+            Was::Unknown => return,
+        };
+    }
+}
+
+impl std::fmt::Debug for ClipGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // what was there could be anything, so leave it out
+        f.debug_struct("ClipGuard").finish_non_exhaustive()
+    }
+}
+
+#[test]
+fn scoped() {
+    crate::testing::with_mock(|mock| {
+        drop(crate::scoped("a"));
+        assert_eq!(mock.text(), None);
+        mock.set_text("mine");
+        let g = crate::scoped("borrowed");
+        assert_eq!(mock.text().as_deref(), Some("borrowed"));
+        drop(g);
+        assert_eq!(mock.text().as_deref(), Some("mine"));
+    });
+}
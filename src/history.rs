@@ -0,0 +1,93 @@
+//! What was copied, newest first. Opt in with the `history` feature.
+//!
+//! Copies made through clipp are recorded, as are changes seen by a [`Watch`](crate::Watch),
+//! so keeping a [`watch`](crate::watch) running records what other programs copy too.
+//! Only text, and only the regular clipboard.
+//!
+//! ```
+//! clipp::copy("one");
+//! clipp::copy("two");
+//! assert_eq!(clipp::history::get(0).as_deref(), Some("two"));
+//! assert_eq!(clipp::history::get(1).as_deref(), Some("one"));
+//! ```
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// a ring buffer, newest at the front
+struct History {
+    entries: VecDeque<String>,
+    capacity: usize,
+}
+
+impl History {
+    const fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        // copying the same thing twice, or our own copy coming back through a watch
+        if self.capacity == 0 || self.entries.front().is_some_and(|e| e == text) {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_back();
+        }
+        self.entries.push_front(text.to_owned());
+    }
+
+    fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.truncate(capacity);
+    }
+}
+
+static HISTORY: Mutex<History> = Mutex::new(History::new(100));
+
+fn history() -> MutexGuard<'static, History> {
+    HISTORY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub(crate) fn record(text: &str) {
+    history().push(text);
+}
+
+/// Everything recorded, newest first.
+#[must_use]
+pub fn entries() -> Vec<String> {
+    history().entries.iter().cloned().collect()
+}
+
+/// The `n`th newest entry. `0` is the last thing copied.
+#[must_use]
+pub fn get(n: usize) -> Option<String> {
+    history().entries.get(n).cloned()
+}
+
+/// How many entries are kept before the oldest are forgotten. Defaults to 100. `0` stops recording.
+pub fn set_capacity(capacity: usize) {
+    history().resize(capacity);
+}
+
+/// Forget everything.
+pub fn clear() {
+    history().entries.clear();
+}
+
+#[test]
+fn ring() {
+    let mut h = History::new(2);
+    for t in ["a", "b", "b", "c"] {
+        h.push(t);
+    }
+    assert_eq!(h.entries, ["c", "b"]);
+    h.resize(1);
+    assert_eq!(h.entries, ["c"]);
+    h.resize(0);
+    h.push("d");
+    assert!(h.entries.is_empty());
+}
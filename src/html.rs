@@ -0,0 +1,123 @@
+//! html as it's pasted: what's in `text/html`, or windows' `HTML Format`, with its header,
+//! and the text and page that came along with it
+use std::borrow::Cow;
+
+/// Html from the clipboard, with what came along with it. See [`paste_html`](crate::paste_html).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Html {
+    /// The html that was copied: the fragment, where the clipboard marks one, else the whole document.
+    pub html: String,
+    /// The plain text version, if there is one.
+    pub plain: Option<String>,
+    /// The page it was copied from, where the browser says.
+    pub source_url: Option<String>,
+}
+
+/// a windows `HTML Format` blob: the fragment (or failing that, the document), and its `SourceURL`
+fn cf_html(data: &[u8]) -> Option<(&[u8], Option<String>)> {
+    if !data.starts_with(b"Version:") {
+        return None;
+    }
+    let end = data.iter().position(|&b| b == b'<').unwrap_or(data.len());
+    let header = std::str::from_utf8(&data[..end]).ok()?;
+    let field = |key: &str| {
+        header
+            .lines()
+            .find_map(|l| l.trim().strip_prefix(key)?.strip_prefix(':'))
+    };
+    let offset = |key: &str| field(key)?.parse::<usize>().ok();
+    let (start, end) = offset("StartFragment")
+        .zip(offset("EndFragment"))
+        .or_else(|| offset("StartHTML").zip(offset("EndHTML")))?;
+    Some((
+        data.get(start..end.min(data.len()))?,
+        field("SourceURL").map(str::to_owned),
+    ))
+}
+
+/// firefox, on x11, gives html as UTF-16, with a byte order mark
+fn decode(data: &[u8]) -> Cow<'_, str> {
+    match data.strip_prefix(b"\xff\xfe") {
+        Some(u) => {
+            let units: Vec<u16> = u
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units).into()
+        }
+        None => String::from_utf8_lossy(data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data)),
+    }
+}
+
+/// what's between the `<!--StartFragment-->` and `<!--EndFragment-->` that windows, and browsers, put around it
+fn fragment(html: &str) -> &str {
+    html.split_once("<!--StartFragment-->")
+        .and_then(|(_, f)| f.rsplit_once("<!--EndFragment-->"))
+        .map_or(html, |(f, _)| f)
+}
+
+/// `data` being `text/html` or `HTML Format`
+pub fn parse(data: &[u8]) -> Html {
+    let (data, source_url) = cf_html(data).unwrap_or((data, None));
+    Html {
+        html: fragment(&decode(data)).to_owned(),
+        plain: None,
+        source_url,
+    }
+}
+
+#[test]
+fn formats() {
+    let doc = "Version:0.9\r\nStartHTML:0000000000\r\nEndHTML:0000000000\r\nStartFragment:0000000000\r\nEndFragment:0000000000\r\nSourceURL:https://example.com/\r\n";
+    let pre = "<html><body>\r\n<!--StartFragment-->";
+    let (start, end) = (doc.len() + pre.len(), doc.len() + pre.len() + 9);
+    let doc = doc
+        .replacen(
+            "StartFragment:0000000000",
+            &format!("StartFragment:{start:010}"),
+            1,
+        )
+        .replacen(
+            "EndFragment:0000000000",
+            &format!("EndFragment:{end:010}"),
+            1,
+        );
+    let h = parse(format!("{doc}{pre}<b>hi</b><!--EndFragment-->\r\n</body></html>").as_bytes());
+    assert_eq!(h.html, "<b>hi</b>");
+    assert_eq!(h.source_url.as_deref(), Some("https://example.com/"));
+    // already out of its header
+    assert_eq!(
+        parse(b"<html><body><!--StartFragment--><i>x</i><!--EndFragment--></body></html>").html,
+        "<i>x</i>"
+    );
+    let utf16: Vec<u8> = [0xfeff]
+        .into_iter()
+        .chain("<b>é</b>".encode_utf16())
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    assert_eq!(parse(&utf16).html, "<b>é</b>");
+    assert_eq!(parse(b"<p>plain</p>").source_url, None);
+}
+
+#[test]
+fn html() {
+    crate::testing::with_mock(|_| {
+        assert_eq!(crate::paste_html(), None);
+        crate::copy_items(&[&[
+            (
+                "text/html",
+                b"<meta charset=\"utf-8\"><!--StartFragment--><b>hi</b><!--EndFragment-->",
+            ),
+            ("text/plain", b"hi"),
+            ("chromium/x-source-url", b"https://example.com/"),
+        ]]);
+        assert_eq!(
+            crate::paste_html(),
+            Some(crate::Html {
+                html: "<b>hi</b>".into(),
+                plain: Some("hi".into()),
+                source_url: Some("https://example.com/".into()),
+            })
+        );
+    });
+}
@@ -0,0 +1,74 @@
+//! data made when it's asked for
+use std::{
+    fmt,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+type Render = Box<dyn FnOnce() -> Vec<u8> + Send>;
+
+/// Data that's only made the first time something pastes it. See [`copy_lazy`](crate::copy_lazy).
+///
+/// Cheap to clone; clones share what's made.
+#[derive(Clone)]
+pub struct Lazy(Arc<Inner>);
+
+struct Inner {
+    render: Mutex<Option<Render>>,
+    made: OnceLock<Arc<[u8]>>,
+}
+
+impl Lazy {
+    /// Data that `render` makes, when it's first needed.
+    pub fn new(render: impl FnOnce() -> Vec<u8> + Send + 'static) -> Self {
+        Self(Arc::new(Inner {
+            render: Mutex::new(Some(Box::new(render))),
+            made: OnceLock::new(),
+        }))
+    }
+
+    /// data that's already made
+    pub(crate) fn made(b: Arc<[u8]>) -> Self {
+        Self(Arc::new(Inner {
+            render: Mutex::new(None),
+            made: OnceLock::from(b),
+        }))
+    }
+
+    /// The data, making it if it hasn't been yet. Empty if making it panicked before.
+    #[must_use]
+    pub fn get(&self) -> Arc<[u8]> {
+        Arc::clone(self.0.made.get_or_init(|| {
+            let render = self
+                .0
+                .render
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            render.map_or_else(|| Arc::from([]), |r| r().into())
+        }))
+    }
+
+    /// Whether it's been made yet.
+    #[must_use]
+    pub fn is_made(&self) -> bool {
+        self.0.made.get().is_some()
+    }
+}
+
+impl fmt::Debug for Lazy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lazy")
+            .field("made", &self.is_made())
+            .finish()
+    }
+}
+
+#[test]
+fn once() {
+    let l = Lazy::new(|| b"made".to_vec());
+    let c = l.clone();
+    assert!(!l.is_made());
+    assert_eq!(&*c.get(), b"made");
+    assert!(l.is_made());
+    assert_eq!(&*l.get(), b"made");
+}
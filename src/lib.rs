@@ -5,19 +5,1663 @@
 //! assert_eq!(clipp::paste(), "wow such clipboard");
 //! ```
 #![warn(clippy::pedantic)]
-#![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "ffi"), forbid(unsafe_code))]
+// only the C interface needs it
+#![cfg_attr(feature = "ffi", deny(unsafe_code))]
+
+/// a [`log`] event from clipp, with the `log` feature. `tracing` picks them up through `tracing-log`.
+/// only as a statement, as it's nothing without the feature
+macro_rules! event {
+    ($level:ident, $($t:tt)+) => {
+        #[cfg(feature = "log")]
+        log::$level!(target: "clipp", $($t)+)
+    };
+}
+
+// no processes to spawn in a browser
+#[cfg(all(feature = "tokio", not(target_family = "wasm")))]
+mod aio;
+mod backend;
+mod clipboard;
+#[cfg(all(feature = "clippd", unix))]
+pub mod clippd;
+#[cfg(all(feature = "config", not(target_family = "wasm")))]
+pub mod config;
+mod error;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod guard;
+#[cfg(feature = "history")]
+pub mod history;
+mod html;
+mod lazy;
 mod providers;
+mod saved;
+#[cfg(all(feature = "sync", not(target_family = "wasm")))]
+pub mod sync;
+pub mod testing;
+mod text;
+mod uri;
+mod watch;
 
-use std::{fmt::Display, sync::OnceLock};
+#[cfg(all(feature = "tokio", not(target_family = "wasm")))]
+pub use aio::{copy_async, events, paste_async, try_events};
+pub use backend::Backend;
+pub use clipboard::{Clipboard, CopyOptions};
+pub use error::{ClipError, ParseOrClipError, Result};
+pub use guard::ClipGuard;
+pub use html::Html;
+pub use lazy::Lazy;
+#[cfg(not(target_family = "wasm"))]
+pub use providers::Commands;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use providers::Stored;
+#[cfg(all(feature = "web", target_family = "wasm"))]
+pub use providers::{copy_async, paste_async};
+pub use providers::{Item, Op, Provider};
+pub use saved::ClipboardSnapshot;
+#[cfg(feature = "secrecy")]
+pub use secrecy::SecretString;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+    time::Duration,
+};
+pub use text::{LineEnding, ToClip};
+pub use uri::FileOp;
+pub use watch::{ChangeToken, ClipboardEvent, SelectionSync, Subscription, Watch};
 
-static CLIP: OnceLock<providers::Board> = OnceLock::new();
+/// Which selection to use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Selection {
+    /// The regular clipboard, as in ctrl+c / ctrl+v.
+    #[default]
+    Clipboard,
+    /// The X11 / Wayland primary selection, as in select / middle click.
+    /// Not available on other platforms.
+    Primary,
+}
 
-/// Copy text to the clipboard.
-pub fn copy(text: impl Display) {
-    CLIP.get_or_init(providers::provide).0(&format!("{text}"));
+static CLIP: OnceLock<Option<Clipboard>> = OnceLock::new();
+
+fn global() -> Result<&'static Clipboard> {
+    CLIP.get_or_init(|| {
+        let c = providers::provide();
+        if c.is_none() {
+            event!(warn, "no clipboard was found");
+        }
+        c.map(Clipboard::of)
+    })
+    .as_ref()
+    .ok_or(ClipError::NoBackend)
+}
+
+/// the mock on this thread, or the shared clipboard
+fn current() -> Result<Clipboard> {
+    match testing::mocked() {
+        Some(c) => Ok(c),
+        None => global().cloned(),
+    }
+}
+
+/// Use `backend` instead of detecting one. Must happen before the clipboard is first used.
+///
+/// The `CLIPP_BACKEND` environment variable does the same, i.e. `CLIPP_BACKEND=xsel`.
+/// `CLIPP_FALLBACK` picks one for when none is found, i.e. `CLIPP_FALLBACK=memory` in CI.
+/// `CLIPP_WSL=0` stops clipp from taking the windows clipboard under WSL, i.e. to use an X server's instead,
+/// and `CLIPP_WSL=1` makes it, if WSL goes unnoticed.
+///
+/// # Errors
+///
+/// If `backend` isn't built, can't start here, or runs a program that isn't installed,
+/// or a clipboard was already chosen.
+pub fn force_backend(backend: Backend) -> Result<()> {
+    let c =
+        providers::Chosen::new(backend, "force_backend was called").ok_or(ClipError::NoBackend)?;
+    CLIP.set(Some(Clipboard::of(c)))
+        .map_err(|_| ClipError::AlreadyChosen)
+}
+
+/// Look for these backends first, in this order, before the rest in the usual order
+/// (clippd when it's running, wsl, the portal when sandboxed, copyq, wayland, x11, xsel, xclip, klipper,
+/// then lemonade, clipper and osc52 over ssh, then tmux, screen, osc52).
+/// Each still has to be usable here, i.e. xclip installed and `DISPLAY` set, or it's passed over.
+/// Must happen before the clipboard is first used, though it also steers falling back.
+///
+/// ```no_run
+/// use clipp::Backend;
+/// // xsel is broken here
+/// clipp::set_priority(&[Backend::XClip, Backend::Klipper]);
+/// ```
+///
+/// The `CLIPP_PRIORITY` environment variable does the same, i.e. `CLIPP_PRIORITY=xclip,klipper`,
+/// unless this was called.
+pub fn set_priority(order: &[Backend]) {
+    providers::set_priority(order);
+}
+
+/// The most copying or pasting may take at once, in bytes, past which it fails with [`ClipError::TooBig`].
+/// [`None`], the default, is no limit.
+///
+/// ```no_run
+/// // a 2 GB clipboard stays where it is
+/// clipp::set_max_size(Some(16 << 20));
+/// ```
+///
+/// Commands are stopped as soon as they print more, so the rest is never read.
+/// [`copy_from_reader`] and [`paste_to_writer`] aren't limited, being the way to move big things.
+pub fn set_max_size(limit: Option<usize>) {
+    providers::set_max_size(limit);
+}
+
+/// How long clipboard commands get to `op` before they're killed and [`ClipError::Timeout`] is returned.
+/// [`None`] waits forever. Defaults to 5 seconds for both.
+///
+/// Applies to backends that run commands, i.e. `xclip` or `powershell.exe`.
+pub fn set_timeout(op: Op, timeout: Option<Duration>) {
+    providers::set_timeout(op, timeout);
+}
+
+/// How many times to try opening the windows clipboard while another program has it open,
+/// waiting `backoff` after the first failure and twice as long after each one after that.
+/// Defaults to 10 tries, from 1ms, so at most about half a second.
+///
+/// Applies to the `windows-native` backend; nothing else has to take turns.
+pub fn set_retries(tries: u32, backoff: Duration) {
+    providers::set_retries(tries, backoff);
+}
+
+/// Turn line endings into `ending` on the way to (for [`Op::Copy`]) or from ([`Op::Paste`]) the clipboard,
+/// i.e. so text copied in windows programs pastes with `\n` under wsl. [`None`], the default, leaves them be.
+///
+/// Applies to text; bytes, html, and other mime types go through as they are.
+pub fn set_line_endings(op: Op, ending: Option<LineEnding>) {
+    text::set_line_endings(op, ending);
+}
+
+/// Which wayland seat to use, by name, i.e. `seat1`, on compositors with several.
+/// [`None`], the default, copies to all of them and pastes from whichever the compositor lists first.
+///
+/// Applies to the `wl-copy` and `wayland` backends.
+pub fn set_seat(seat: Option<&str>) {
+    providers::set_seat(seat);
+}
+
+/// The mime types [`paste`] takes the text from where the clipboard has them, richest first,
+/// i.e. markdown over html over the plain text. Empty, the default, is the plain text.
+///
+/// ```no_run
+/// clipp::set_preferred(&["text/markdown", "text/html"]);
+/// // what it's pasted into can take html
+/// let html_or_text = clipp::paste();
+/// ```
+///
+/// The first one that's there wins, and anything after the plain text (`text/plain`) is never used.
+/// What isn't UTF-8 is left for the text. Applies to [`paste`] and [`Clipboard::paste_from`], from any selection;
+/// [`paste_bytes`] and [`paste_mime`] paste what they're asked for.
+///
+/// The `CLIPP_PREFER` environment variable does the same, i.e. `CLIPP_PREFER=text/markdown,text/html`,
+/// unless this was called.
+pub fn set_preferred(order: &[&str]) {
+    providers::set_preferred(order);
+}
+
+/// Keep helper processes running between calls, instead of starting one every time.
+/// Off by default. Turning it off stops them.
+///
+/// Starting powershell (on windows without `windows-native`, and under wsl) takes a good while,
+/// so with this on one is kept, and copy and paste become a line written to it.
+/// The native backends (`x11`, `wayland-native`, `windows-native`, `macos-native`, `portal`) never start anything.
+pub fn set_persistent(on: bool) {
+    providers::set_persistent(on);
+}
+
+/// Use your own clipboard instead of detecting one. Must happen before the clipboard is first used.
+/// [`backend_name`] will say `"custom"`.
+///
+/// ```
+/// use clipp::{Provider, Result, Selection};
+/// use std::sync::Mutex;
+///
+/// struct Register(Mutex<Vec<u8>>);
+/// impl Provider for Register {
+///     fn clear(&self, _: Selection) -> Result<()> {
+///         self.0.lock().unwrap().clear();
+///         Ok(())
+///     }
+///
+///     fn copy_bytes(&self, _: Selection, bytes: &[u8]) -> Result<()> {
+///         *self.0.lock().unwrap() = bytes.to_vec();
+///         Ok(())
+///     }
+///
+///     fn paste_bytes(&self, _: Selection) -> Result<Vec<u8>> {
+///         Ok(self.0.lock().unwrap().clone())
+///     }
+/// }
+///
+/// clipp::set_provider(Register(Mutex::new(vec![]))).unwrap();
+/// clipp::copy("in the register");
+/// assert_eq!(clipp::paste(), "in the register");
+/// ```
+///
+/// # Errors
+///
+/// If a clipboard was already chosen.
+pub fn set_provider(provider: impl Provider + 'static) -> Result<()> {
+    CLIP.set(Some(Clipboard::with_provider(provider)))
+        .map_err(|_| ClipError::AlreadyChosen)
+}
+
+/// The name of the clipboard backend in use, i.e. `"wl-copy"` or `"windows"`. See [`Backend::name`].
+/// Picks one, if that hasn't happened yet.
+/// [`None`] if there is no clipboard.
+#[must_use]
+pub fn backend_name() -> Option<&'static str> {
+    current().ok().as_ref().map(Clipboard::backend_name)
+}
+
+/// Why the clipboard backend in use was picked, i.e. `"WAYLAND_DISPLAY is set and wl-copy is installed"`.
+/// Handy in bug reports.
+/// [`None`] if there is no clipboard.
+#[must_use]
+pub fn backend_reason() -> Option<&'static str> {
+    current().ok().as_ref().map(Clipboard::backend_reason)
+}
+
+/// The clipboard backends that broke and were fallen back from, oldest first. See [`Clipboard`].
+/// Empty if none have, or there is no clipboard.
+#[must_use]
+pub fn failed_backends() -> Vec<&'static str> {
+    current().map(|c| c.failed_backends()).unwrap_or_default()
+}
+
+/// Whether there is a clipboard to copy to and paste from, i.e. to grey out a copy button when there isn't.
+/// Picks one, if that hasn't happened yet. Never panics.
+///
+/// ```
+/// if !clipp::is_available() {
+///     eprintln!("no clipboard here");
+/// }
+/// ```
+#[must_use]
+pub fn is_available() -> bool {
+    current().is_ok()
+}
+
+/// Copy text to the clipboard. Takes strings, numbers, and [`format_args!`]; see [`ToClip`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy`].
+pub fn copy(text: impl ToClip) {
+    copy_to(Selection::Clipboard, text);
 }
 
 /// Paste text from the clipboard.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste`].
+#[must_use]
 pub fn paste() -> String {
-    CLIP.get_or_init(providers::provide).1()
+    paste_from(Selection::Clipboard)
+}
+
+/// Copy text to the given selection.
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_to`].
+pub fn copy_to(sel: Selection, text: impl ToClip) {
+    try_copy_to(sel, &text.to_clip()).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Paste text from the given selection.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_from`].
+#[must_use]
+pub fn paste_from(sel: Selection) -> String {
+    try_paste_from(sel).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Copy text to the clipboard.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the clipboard backend fails.
+pub fn try_copy(text: &str) -> Result<()> {
+    try_copy_to(Selection::Clipboard, text)
+}
+
+/// Replace the clipboard with what `f` makes of it. See [`Clipboard::transform`].
+///
+/// ```
+/// clipp::copy("  shout  ");
+/// clipp::transform(|s| s.trim().to_uppercase());
+/// assert_eq!(clipp::paste(), "SHOUT");
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or the backend fails. See [`try_transform`].
+pub fn transform(f: impl FnMut(&str) -> String) {
+    try_transform(f).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Replace the clipboard with what `f` makes of it. See [`Clipboard::transform`].
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend fails, or what's there isn't text.
+pub fn try_transform(f: impl FnMut(&str) -> String) -> Result<()> {
+    current()?.transform(f)
+}
+
+/// Add `text` to the end of the clipboard, with `sep` in between unless the clipboard is empty.
+///
+/// ```
+/// clipp::copy("one");
+/// clipp::append("two", ", ");
+/// assert_eq!(clipp::paste(), "one, two");
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or the backend fails. See [`try_append`].
+pub fn append(text: &str, sep: &str) {
+    try_append(text, sep).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Add `text` to the end of the clipboard, with `sep` in between unless the clipboard is empty.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend fails, or what's there isn't text.
+pub fn try_append(text: &str, sep: &str) -> Result<()> {
+    current()?.append(text, sep)
+}
+
+/// Add `text` to the start of the clipboard, with `sep` in between unless the clipboard is empty.
+///
+/// # Panics
+///
+/// If no clipboard is available, or the backend fails. See [`try_prepend`].
+pub fn prepend(text: &str, sep: &str) {
+    try_prepend(text, sep).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Add `text` to the start of the clipboard, with `sep` in between unless the clipboard is empty.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend fails, or what's there isn't text.
+pub fn try_prepend(text: &str, sep: &str) -> Result<()> {
+    current()?.prepend(text, sep)
+}
+
+/// Copy text to the clipboard until the returned guard is dropped, then put back what was there.
+/// For automation that pastes by pressing keys, and wants to leave the clipboard as it found it.
+///
+/// ```
+/// clipp::copy("mine");
+/// {
+///     let _guard = clipp::scoped("borrowed");
+///     assert_eq!(clipp::paste(), "borrowed");
+/// }
+/// assert_eq!(clipp::paste(), "mine");
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_scoped`].
+pub fn scoped(text: &str) -> ClipGuard {
+    try_scoped(text).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Copy text to the clipboard until the returned guard is dropped. See [`scoped`].
+///
+/// # Errors
+///
+/// If no clipboard is available, or copying fails.
+pub fn try_scoped(text: &str) -> Result<ClipGuard> {
+    current()?.scoped(text)
+}
+
+/// Copy text to the clipboard, returning what was there before.
+///
+/// ```
+/// let old = clipp::swap("borrowed");
+/// // ... paste it somewhere ...
+/// clipp::copy(old);
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or swapping fails. See [`try_swap`].
+#[must_use = "it returns what was on the clipboard"]
+pub fn swap(text: &str) -> String {
+    try_swap(text).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Copy text to the clipboard, returning what was there before. See [`Clipboard::swap`].
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend fails, or what was there isn't text.
+pub fn try_swap(text: &str) -> Result<String> {
+    current()?.swap(text)
+}
+
+/// Copy text to the clipboard, marked so clipboard managers leave it out. See [`Clipboard::copy_sensitive`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_sensitive`].
+pub fn copy_sensitive(text: &str) {
+    try_copy_sensitive(text).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy text to the clipboard, marked so clipboard managers leave it out. See [`Clipboard::copy_sensitive`].
+///
+/// # Errors
+///
+/// If no clipboard is available, or the clipboard backend fails.
+pub fn try_copy_sensitive(text: &str) -> Result<()> {
+    current()?.copy_sensitive(text)
+}
+
+/// Copy text to the clipboard, kept out of the history or off other devices as `options` say.
+/// See [`Clipboard::copy_with`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_with`].
+pub fn copy_with(text: &str, options: CopyOptions) {
+    try_copy_with(text, options).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy text to the clipboard, kept out of the history or off other devices as `options` say.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the clipboard backend fails.
+pub fn try_copy_with(text: &str, options: CopyOptions) -> Result<()> {
+    current()?.copy_with(text, options)
+}
+
+/// Copy a secret to the clipboard, marked so clipboard managers leave it out. See [`Clipboard::copy_secret`].
+///
+/// ```no_run
+/// let password = clipp::SecretString::from("hunter2");
+/// clipp::copy_secret(&password)?;
+/// # Ok::<(), clipp::ClipError>(())
+/// ```
+///
+/// # Errors
+///
+/// If no clipboard is available, or the clipboard backend fails.
+#[cfg(feature = "secrecy")]
+pub fn copy_secret(secret: &SecretString) -> Result<()> {
+    current()?.copy_secret(secret)
+}
+
+/// Copy text to the clipboard, and clear it after `ttl` if it's still there. See [`Clipboard::copy_for`].
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// clipp::copy_for("hunter2", Duration::from_secs(30));
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_for`].
+pub fn copy_for(text: &str, ttl: Duration) {
+    try_copy_for(text, ttl).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy text to the clipboard, and clear it after `ttl` if it's still there. See [`Clipboard::copy_for`].
+///
+/// # Errors
+///
+/// If no clipboard is available, or the clipboard backend fails.
+pub fn try_copy_for(text: &str, ttl: Duration) -> Result<()> {
+    current()?.copy_for(text, ttl)
+}
+
+/// Copy text to the clipboard so it stays there after this program exits. For short lived command line tools.
+///
+/// The native x11 and wayland backends, and the portal, serve the clipboard from this process,
+/// so what they copy is gone when it exits. This hands the text to `wl-copy`, `xclip`, or `xsel` instead,
+/// which stay in the background. Other backends just copy.
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_persistent`].
+pub fn copy_persistent(text: &str) {
+    try_copy_persistent(text).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy text to the clipboard so it stays there after this program exits.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend serves the clipboard from this process and none of
+/// `wl-copy`, `xclip`, and `xsel` are installed, or copying fails.
+pub fn try_copy_persistent(text: &str) -> Result<()> {
+    current()?.copy_persistent(text)
+}
+
+/// Paste text from the clipboard.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the clipboard backend fails.
+pub fn try_paste() -> Result<String> {
+    try_paste_from(Selection::Clipboard)
+}
+
+/// Copy text to the given selection.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend doesn't have this selection, or the backend fails.
+pub fn try_copy_to(sel: Selection, text: &str) -> Result<()> {
+    current()?.copy_to(sel, text)
+}
+
+/// Paste text from the given selection.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend doesn't have this selection, or the backend fails.
+pub fn try_paste_from(sel: Selection) -> Result<String> {
+    current()?.paste_from(sel)
+}
+
+/// Paste, and parse it, ignoring whitespace around it.
+///
+/// ```
+/// clipp::copy("42\n");
+/// assert_eq!(clipp::paste_parse::<u64>().unwrap(), 42);
+/// ```
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend fails, or it doesn't parse.
+pub fn paste_parse<T: FromStr>() -> Result<T, ParseOrClipError<T::Err>> {
+    current()?.paste_parse()
+}
+
+/// Copy `value` to the clipboard, as JSON.
+///
+/// ```
+/// clipp::copy_json(&[1, 2, 3])?;
+/// assert_eq!(clipp::paste_json::<Vec<u8>>()?, [1, 2, 3]);
+/// # Ok::<(), clipp::ParseOrClipError<serde_json::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// If no clipboard is available, it doesn't serialize, or the backend fails.
+#[cfg(feature = "serde")]
+pub fn copy_json<T: serde::Serialize + ?Sized>(
+    value: &T,
+) -> Result<(), ParseOrClipError<serde_json::Error>> {
+    current()?.copy_json(value)
+}
+
+/// Paste JSON from the clipboard.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend fails, or it doesn't deserialize to `T`.
+#[cfg(feature = "serde")]
+pub fn paste_json<T: serde::de::DeserializeOwned>() -> Result<T, ParseOrClipError<serde_json::Error>>
+{
+    current()?.paste_json()
+}
+
+/// Clear the clipboard.
+///
+/// # Panics
+///
+/// If no clipboard is available, or clearing fails. See [`try_clear`].
+pub fn clear() {
+    try_clear().unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Clear the clipboard.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the clipboard backend fails.
+pub fn try_clear() -> Result<()> {
+    current()?.clear()
+}
+
+/// Clear the given selection.
+///
+/// # Panics
+///
+/// If no clipboard is available, or clearing fails. See [`try_clear_selection`].
+pub fn clear_selection(sel: Selection) {
+    try_clear_selection(sel).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Clear the given selection.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend doesn't have this selection, or the backend fails.
+pub fn try_clear_selection(sel: Selection) -> Result<()> {
+    current()?.clear_selection(sel)
+}
+
+/// Copy raw bytes to the clipboard.
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_bytes`].
+pub fn copy_bytes(bytes: &[u8]) {
+    try_copy_bytes(bytes).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Paste raw bytes from the clipboard.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_bytes`].
+#[must_use]
+pub fn paste_bytes() -> Vec<u8> {
+    try_paste_bytes().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Copy raw bytes to the clipboard.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+/// Text only backends (windows, klipper) will refuse bytes that aren't UTF-8.
+pub fn try_copy_bytes(bytes: &[u8]) -> Result<()> {
+    current()?.copy_bytes(bytes)
+}
+
+/// Paste raw bytes from the clipboard.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+pub fn try_paste_bytes() -> Result<Vec<u8>> {
+    current()?.paste_bytes()
+}
+
+/// Paste text from the clipboard, with anything that isn't UTF-8 replaced, rather than failing.
+/// See [`Clipboard::paste_lossy`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_lossy`].
+#[must_use]
+pub fn paste_lossy() -> String {
+    try_paste_lossy().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Paste text from the clipboard, with anything that isn't UTF-8 replaced.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+pub fn try_paste_lossy() -> Result<String> {
+    current()?.paste_lossy()
+}
+
+/// Paste text from the clipboard, decoding it from a legacy encoding if it isn't UTF-8.
+/// See [`Clipboard::paste_decoded`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_decoded`].
+#[cfg(feature = "encoding")]
+#[must_use]
+pub fn paste_decoded() -> String {
+    try_paste_decoded().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Paste text from the clipboard, decoding it from a legacy encoding if it isn't UTF-8.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+#[cfg(feature = "encoding")]
+pub fn try_paste_decoded() -> Result<String> {
+    current()?.paste_decoded()
+}
+
+/// Paste text from the clipboard as an [`OsString`](std::ffi::OsString). See [`Clipboard::paste_os_string`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_os_string`].
+#[must_use]
+pub fn paste_os_string() -> std::ffi::OsString {
+    try_paste_os_string().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Paste text from the clipboard as an [`OsString`](std::ffi::OsString).
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+pub fn try_paste_os_string() -> Result<std::ffi::OsString> {
+    current()?.paste_os_string()
+}
+
+/// Copy everything `r` reads to the clipboard, as it's read. See [`Clipboard::copy_from_reader`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or reading or copying fails. See [`try_copy_from_reader`].
+pub fn copy_from_reader(r: impl std::io::Read) {
+    try_copy_from_reader(r).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Paste raw bytes from the clipboard into `w`, as they come. See [`Clipboard::paste_to_writer`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting or writing fails. See [`try_paste_to_writer`].
+pub fn paste_to_writer(w: impl std::io::Write) {
+    try_paste_to_writer(w).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy everything `r` reads to the clipboard, as it's read.
+///
+/// # Errors
+///
+/// If no clipboard is available, reading fails, or the backend fails.
+pub fn try_copy_from_reader(r: impl std::io::Read) -> Result<()> {
+    current()?.copy_from_reader(r)
+}
+
+/// Paste raw bytes from the clipboard into `w`, as they come.
+///
+/// # Errors
+///
+/// If no clipboard is available, writing fails, or the backend fails.
+pub fn try_paste_to_writer(w: impl std::io::Write) -> Result<()> {
+    current()?.paste_to_writer(w)
+}
+
+/// Paste text from the clipboard onto the end of `buf`, reusing its allocation. Handy in loops.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_into`].
+pub fn paste_into(buf: &mut String) {
+    try_paste_into(buf).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Paste text from the clipboard onto the end of `buf`, reusing its allocation.
+/// `buf` is left as it was if this fails.
+///
+/// # Errors
+///
+/// If no clipboard is available, pasting fails, or the text isn't UTF-8.
+pub fn try_paste_into(buf: &mut String) -> Result<()> {
+    current()?.paste_into(buf)
+}
+
+/// Paste raw bytes from the clipboard onto the end of `buf`, reusing its allocation.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_bytes_into`].
+pub fn paste_bytes_into(buf: &mut Vec<u8>) {
+    try_paste_bytes_into(buf).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Paste raw bytes from the clipboard onto the end of `buf`, reusing its allocation.
+/// `buf` is left as it was if this fails.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+pub fn try_paste_bytes_into(buf: &mut Vec<u8>) -> Result<()> {
+    current()?.paste_bytes_into(buf)
+}
+
+/// Copy data of the given mime type (i.e. `text/html`) to the clipboard.
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_with_mime`].
+pub fn copy_with_mime(mime: &str, data: &[u8]) {
+    try_copy_with_mime(mime, data).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy data of the given mime type that `render` makes when something first pastes it,
+/// for big things that might never be pasted. Never goes in [`history`].
+///
+/// ```
+/// clipp::copy_lazy("text/plain", || {
+///     // only when pasted
+///     (0..1000).map(|n| format!("{n}\n")).collect::<String>().into_bytes()
+/// });
+/// ```
+///
+/// Needs a backend that serves the clipboard from this process (the native x11 one, the portal, and memory).
+/// Others get the data right away.
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_lazy`].
+pub fn copy_lazy(mime: &str, render: impl FnOnce() -> Vec<u8> + Send + 'static) {
+    try_copy_lazy(mime, render).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy data of the given mime type that `render` makes when something first pastes it.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do mime types, or the backend fails.
+pub fn try_copy_lazy(mime: &str, render: impl FnOnce() -> Vec<u8> + Send + 'static) -> Result<()> {
+    current()?.copy_lazy(mime, render)
+}
+
+/// Paste data of the given mime type from the clipboard, if there is any.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_mime`].
+#[must_use]
+pub fn paste_mime(mime: &str) -> Option<Vec<u8>> {
+    try_paste_mime(mime).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Copy data of the given mime type (i.e. `text/html`) to the clipboard.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do mime types, or the backend fails.
+pub fn try_copy_with_mime(mime: &str, data: &[u8]) -> Result<()> {
+    current()?.copy_with_mime(mime, data)
+}
+
+/// Paste data of the given mime type from the clipboard.
+/// Returns [`None`] if the clipboard doesn't hold that type.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do mime types, or the backend fails.
+pub fn try_paste_mime(mime: &str) -> Result<Option<Vec<u8>>> {
+    current()?.paste_mime(mime)
+}
+
+/// How many bytes of text the clipboard holds, without pasting it. [`None`] if the backend can't tell.
+/// See [`Clipboard::paste_len`].
+///
+/// ```no_run
+/// if let Some(n) = clipp::paste_len() {
+///     println!("clipboard: {} kB", n / 1000);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or the backend fails. See [`try_paste_len`].
+#[must_use]
+pub fn paste_len() -> Option<usize> {
+    try_paste_len().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// How many bytes of text the clipboard holds, without pasting it. [`None`] if the backend can't tell.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+pub fn try_paste_len() -> Result<Option<usize>> {
+    current()?.paste_len()
+}
+
+/// How many bytes of data of the given mime type the clipboard holds, without pasting it.
+/// See [`Clipboard::paste_mime_len`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or the backend fails. See [`try_paste_mime_len`].
+#[must_use]
+pub fn paste_mime_len(mime: &str) -> Option<usize> {
+    try_paste_mime_len(mime).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// How many bytes of data of the given mime type the clipboard holds, without pasting it.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+pub fn try_paste_mime_len(mime: &str) -> Result<Option<usize>> {
+    current()?.paste_mime_len(mime)
+}
+
+/// The formats the clipboard holds, i.e. `["text/html", "text/plain"]`.
+///
+/// # Panics
+///
+/// If no clipboard is available, or listing fails. See [`try_available_formats`].
+#[must_use]
+pub fn available_formats() -> Vec<String> {
+    try_available_formats().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// The formats the clipboard holds: mime types where there is one,
+/// and the platform's own names otherwise. Empty if it holds nothing.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't list formats, or the backend fails.
+pub fn try_available_formats() -> Result<Vec<String>> {
+    current()?.available_formats()
+}
+
+/// Copy a PNG image to the clipboard.
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_image`].
+pub fn copy_image(png: &[u8]) {
+    try_copy_image(png).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Paste a PNG image from the clipboard, if there is one.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_image`].
+#[must_use]
+pub fn paste_image() -> Option<Vec<u8>> {
+    try_paste_image().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Copy a PNG image to the clipboard.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do images, or the backend fails.
+pub fn try_copy_image(png: &[u8]) -> Result<()> {
+    current()?.copy_image(png)
+}
+
+/// Paste a PNG image from the clipboard.
+/// Returns [`None`] if the clipboard doesn't hold an image.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do images, or the backend fails.
+pub fn try_paste_image() -> Result<Option<Vec<u8>>> {
+    current()?.paste_image()
+}
+
+/// Copy an [`image::DynamicImage`] to the clipboard, encoding it as PNG (and a bitmap on windows).
+/// See [`Clipboard::copy_dynamic_image`].
+///
+/// ```no_run
+/// let img = image::DynamicImage::new_rgba8(16, 16);
+/// clipp::copy_dynamic_image(&img)?;
+/// # Ok::<(), clipp::ParseOrClipError<image::ImageError>>(())
+/// ```
+///
+/// # Errors
+///
+/// If no clipboard is available, it doesn't encode, the backend can't do images, or the backend fails.
+#[cfg(feature = "image")]
+pub fn copy_dynamic_image(
+    image: &image::DynamicImage,
+) -> Result<(), ParseOrClipError<image::ImageError>> {
+    current()?.copy_dynamic_image(image)
+}
+
+/// Paste an image from the clipboard, decoded. Returns [`None`] if the clipboard doesn't hold an image.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do images, the backend fails, or the image doesn't decode.
+#[cfg(feature = "image")]
+pub fn paste_dynamic_image(
+) -> Result<Option<image::DynamicImage>, ParseOrClipError<image::ImageError>> {
+    current()?.paste_dynamic_image()
+}
+
+/// Copy html to the clipboard, along with a plain text version for things that can't take html.
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_html`].
+pub fn copy_html(html: &str, plain: &str) {
+    try_copy_html(html, plain).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy html to the clipboard, along with a plain text version for things that can't take html.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't hold several formats at once, or the backend fails.
+pub fn try_copy_html(html: &str, plain: &str) -> Result<()> {
+    current()?.copy_html(html, plain)
+}
+
+/// Paste html from the clipboard, with its plain text version and the page it came from.
+/// Returns [`None`] if the clipboard doesn't hold html.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_html`].
+#[must_use]
+pub fn paste_html() -> Option<Html> {
+    try_paste_html().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Paste html from the clipboard, with its plain text version and the page it came from. See [`Clipboard::paste_html`].
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't paste formats, or the backend fails.
+pub fn try_paste_html() -> Result<Option<Html>> {
+    current()?.paste_html()
+}
+
+/// Copy markdown to the clipboard as rich text: rendered to html, with the markdown itself as the plain text.
+///
+/// ```no_run
+/// clipp::copy_markdown("# notes\n- **one**\n- two");
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_markdown`].
+#[cfg(feature = "markdown")]
+pub fn copy_markdown(md: &str) {
+    try_copy_markdown(md).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy markdown to the clipboard as rich text. See [`Clipboard::copy_markdown`].
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't hold several formats at once, or the backend fails.
+#[cfg(feature = "markdown")]
+pub fn try_copy_markdown(md: &str) -> Result<()> {
+    current()?.copy_markdown(md)
+}
+
+/// Copy rich text to the clipboard, along with a plain text version for things that can't take RTF.
+///
+/// ```no_run
+/// clipp::copy_rtf(r"{\rtf1\ansi {\b bold} move}", "bold move");
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_rtf`].
+pub fn copy_rtf(rtf: &str, plain: &str) {
+    try_copy_rtf(rtf, plain).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Copy rich text to the clipboard, along with a plain text version for things that can't take RTF.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't hold several formats at once, or the backend fails.
+pub fn try_copy_rtf(rtf: &str, plain: &str) -> Result<()> {
+    current()?.copy_rtf(rtf, plain)
+}
+
+/// Copy a list of files to the clipboard, for pasting into file managers.
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_files`].
+pub fn copy_files(paths: &[PathBuf]) {
+    try_copy_files(paths).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Paste a list of files from the clipboard. Empty if there are none.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_files`].
+#[must_use]
+pub fn paste_files() -> Vec<PathBuf> {
+    try_paste_files().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Copy a list of files to the clipboard, for pasting into file managers.
+/// Relative paths are made absolute.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do files, or the backend fails.
+pub fn try_copy_files(paths: &[PathBuf]) -> Result<()> {
+    current()?.copy_files(paths)
+}
+
+/// Paste a list of files from the clipboard. Empty if there are none.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do files, or the backend fails.
+pub fn try_paste_files() -> Result<Vec<PathBuf>> {
+    current()?.paste_files()
+}
+
+/// Copy a list of files to the clipboard, cut, so pasting them in a file manager moves them.
+///
+/// ```no_run
+/// clipp::cut_files(&["notes.txt".into()]);
+/// // and in nautilus, dolphin, or explorer, ctrl+v moves it
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_cut_files`].
+pub fn cut_files(paths: &[PathBuf]) {
+    try_cut_files(paths).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Paste a list of files from the clipboard, and whether they were copied or cut.
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_files_op`].
+#[must_use]
+pub fn paste_files_op() -> (Vec<PathBuf>, FileOp) {
+    try_paste_files_op().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Copy a list of files to the clipboard, cut, so pasting them in a file manager moves them.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do files or mark them cut, or the backend fails.
+pub fn try_cut_files(paths: &[PathBuf]) -> Result<()> {
+    current()?.cut_files(paths)
+}
+
+/// Paste a list of files from the clipboard, and whether they were copied or cut.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't do files, or the backend fails.
+pub fn try_paste_files_op() -> Result<(Vec<PathBuf>, FileOp)> {
+    current()?.paste_files_op()
+}
+
+/// Copy several things to the clipboard at once, each in several formats. See [`Clipboard::copy_items`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or copying fails. See [`try_copy_items`].
+pub fn copy_items(items: &[&[(&str, &[u8])]]) {
+    try_copy_items(items).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Everything on the clipboard, each thing in every format it's in. See [`Clipboard::paste_items`].
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_items`].
+#[must_use]
+pub fn paste_items() -> Vec<Item> {
+    try_paste_items().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Copy several things to the clipboard at once, each in several formats.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't hold that many things or formats, or the backend fails.
+pub fn try_copy_items(items: &[&[(&str, &[u8])]]) -> Result<()> {
+    current()?.copy_items(items)
+}
+
+/// Everything on the clipboard, each thing in every format it's in.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't list formats, or the backend fails.
+pub fn try_paste_items() -> Result<Vec<Item>> {
+    current()?.paste_items()
+}
+
+/// Everything on the clipboard, in every format, for [`ClipboardSnapshot::restore`] to put back,
+/// where [`scoped`]'s guard only puts back the text. For tools that copy something for a moment.
+///
+/// ```
+/// # clipp::testing::with_mock(|_| {
+/// clipp::copy_html("<b>rich</b>", "rich");
+/// let was = clipp::snapshot();
+/// clipp::copy("for a moment");
+/// was.restore().unwrap();
+/// assert_eq!(clipp::paste_mime("text/html").unwrap(), b"<b>rich</b>");
+/// # });
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_snapshot`].
+#[must_use]
+pub fn snapshot() -> ClipboardSnapshot {
+    try_snapshot().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Everything on the clipboard, in every format, to put back later.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't list formats, or the backend fails.
+pub fn try_snapshot() -> Result<ClipboardSnapshot> {
+    current()?.snapshot()
+}
+
+/// Write everything on the clipboard, in every format, to `path`, for [`restore`] to put back,
+/// after a reboot if need be. `clippd --restore` does it by itself, as the clipboard changes.
+///
+/// ```
+/// # clipp::testing::with_mock(|_| {
+/// let path = std::env::temp_dir().join("clipp-saved-doc");
+/// clipp::copy("kept");
+/// clipp::save(&path);
+/// clipp::copy("something else");
+/// clipp::restore(&path);
+/// assert_eq!(clipp::paste(), "kept");
+/// # std::fs::remove_file(path).unwrap();
+/// # });
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, pasting fails, or `path` can't be written. See [`try_save`].
+pub fn save(path: impl AsRef<Path>) {
+    try_save(path).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Put back what [`save`] wrote to `path`, in every format the backend can hold at once, or at least the text.
+///
+/// # Panics
+///
+/// If no clipboard is available, `path` can't be read or wasn't written by [`save`], or copying fails.
+/// See [`try_restore`].
+pub fn restore(path: impl AsRef<Path>) {
+    try_restore(path).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Write everything on the clipboard, in every format, to `path`.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend can't list formats, the backend fails, or `path` can't be written.
+pub fn try_save(path: impl AsRef<Path>) -> Result<()> {
+    current()?.save(path)
+}
+
+/// Put back what [`save`] wrote to `path`.
+///
+/// # Errors
+///
+/// If no clipboard is available, `path` can't be read or wasn't written by [`save`], or the backend fails.
+pub fn try_restore(path: impl AsRef<Path>) -> Result<()> {
+    current()?.restore(path)
+}
+
+/// Where the clipboard is at, to ask [`has_changed_since`] later.
+///
+/// ```
+/// # clipp::testing::with_mock(|_| {
+/// let token = clipp::change_token();
+/// clipp::copy("new");
+/// assert!(clipp::has_changed_since(token));
+/// # });
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or the backend fails. See [`try_change_token`].
+#[must_use]
+pub fn change_token() -> ChangeToken {
+    try_change_token().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Whether the clipboard changed since `token` was taken.
+///
+/// # Panics
+///
+/// If no clipboard is available, or the backend fails. See [`try_has_changed_since`].
+#[must_use]
+pub fn has_changed_since(token: ChangeToken) -> bool {
+    try_has_changed_since(token).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Where the clipboard is at, to ask [`try_has_changed_since`] later.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+pub fn try_change_token() -> Result<ChangeToken> {
+    current()?.change_token()
+}
+
+/// Whether the clipboard changed since `token` was taken.
+/// Cheap on windows and macos, where the platform counts changes; elsewhere this pastes.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+pub fn try_has_changed_since(token: ChangeToken) -> Result<bool> {
+    current()?.has_changed_since(token)
+}
+
+/// A hash of what's on the clipboard, for pollers to compare. See [`Clipboard::paste_hash`].
+///
+/// ```
+/// # clipp::testing::with_mock(|_| {
+/// clipp::copy("same");
+/// let last = clipp::paste_hash();
+/// clipp::copy("same");
+/// assert_eq!(clipp::paste_hash(), last);
+/// # });
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or pasting fails. See [`try_paste_hash`].
+#[must_use]
+pub fn paste_hash() -> u64 {
+    try_paste_hash().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// A hash of what's on the clipboard, for pollers to compare.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the backend fails.
+pub fn try_paste_hash() -> Result<u64> {
+    current()?.paste_hash()
+}
+
+/// Watch the clipboard for changes, yielding the new text each time it changes.
+///
+/// ```no_run
+/// for text in clipp::watch() {
+///     println!("copied {text}");
+/// }
+/// ```
+///
+/// Windows, x11, wayland, and the portal say when it changes; macos's change count is read
+/// [`every`](Watch::every) so often; anything else is pasted that often.
+/// Ends if no clipboard is available.
+#[must_use]
+pub fn watch() -> Watch {
+    match current() {
+        Ok(c) => c.watch(),
+        Err(_) => Watch::new(None, Selection::Clipboard),
+    }
+}
+
+/// Call `f` whenever the clipboard changes, from a background thread, until the returned handle is dropped.
+/// For GUI event loops, where [`watch`]'s blocking iterator doesn't fit: send the event to the loop from `f`.
+///
+/// ```no_run
+/// let _sub = clipp::on_change(|e| println!("copied {}", e.text));
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or the thread can't be started. See [`try_on_change`].
+pub fn on_change(f: impl FnMut(ClipboardEvent) + Send + 'static) -> Subscription {
+    try_on_change(f).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Call `f` whenever the clipboard changes, from a background thread, until the returned handle is dropped.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the thread can't be started.
+pub fn try_on_change(f: impl FnMut(ClipboardEvent) + Send + 'static) -> Result<Subscription> {
+    current()?.on_change(f)
+}
+
+/// Copy whatever lands in the primary selection to the clipboard, and the other way around,
+/// on background threads, until the returned handle is dropped. What autocutsel does.
+///
+/// ```no_run
+/// let _sync = clipp::sync_selections();
+/// // select something, and ctrl+v pastes it
+/// ```
+///
+/// # Panics
+///
+/// If no clipboard is available, or syncing can't start. See [`try_sync_selections`].
+pub fn sync_selections() -> SelectionSync {
+    try_sync_selections().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Keep the primary selection and the clipboard the same until the returned handle is dropped.
+///
+/// # Errors
+///
+/// If no clipboard is available, the backend doesn't have a primary selection (windows, macos), or the threads can't be started.
+pub fn try_sync_selections() -> Result<SelectionSync> {
+    current()?.sync_selections()
+}
+
+/// Everything KDE's klipper remembers, newest first; the first is what's on the clipboard now.
+/// For offering "paste from history" on plasma, with the history the user already has.
+///
+/// ```no_run
+/// for (i, text) in clipp::klipper_history().iter().enumerate() {
+///     println!("{i}: {text}");
+/// }
+/// ```
+///
+/// # Panics
+///
+/// If klipper isn't running. See [`try_klipper_history`].
+#[cfg(all(feature = "klipper", unix, not(target_os = "macos")))]
+#[must_use]
+pub fn klipper_history() -> Vec<String> {
+    try_klipper_history().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Everything KDE's klipper remembers, newest first.
+///
+/// # Errors
+///
+/// If klipper isn't running (or, without `klipper-native`, qdbus isn't installed), or it doesn't answer.
+#[cfg(all(feature = "klipper", unix, not(target_os = "macos")))]
+pub fn try_klipper_history() -> Result<Vec<String>> {
+    providers::klipper_history()
+}
+
+/// The `n`th thing KDE's klipper remembers, 0 being what's on the clipboard now, or [`None`] past the end.
+///
+/// # Panics
+///
+/// If klipper isn't running. See [`try_klipper_history_item`].
+#[cfg(all(feature = "klipper", unix, not(target_os = "macos")))]
+#[must_use]
+pub fn klipper_history_item(n: usize) -> Option<String> {
+    try_klipper_history_item(n).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// The `n`th thing KDE's klipper remembers, or [`None`] past the end.
+///
+/// # Errors
+///
+/// If klipper isn't running (or, without `klipper-native`, qdbus isn't installed), or it doesn't answer.
+#[cfg(all(feature = "klipper", unix, not(target_os = "macos")))]
+pub fn try_klipper_history_item(n: usize) -> Result<Option<String>> {
+    providers::klipper_history_item(n)
+}
+
+/// Everything in copyq's first tab, newest first, as text.
+///
+/// ```no_run
+/// let last = clipp::copyq_history().into_iter().nth(1);
+/// ```
+///
+/// # Panics
+///
+/// If copyq isn't running. See [`try_copyq_history`].
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+#[must_use]
+pub fn copyq_history() -> Vec<String> {
+    try_copyq_history().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Everything in copyq's first tab, newest first, as text.
+///
+/// # Errors
+///
+/// If copyq isn't installed or running, or it fails.
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+pub fn try_copyq_history() -> Result<Vec<String>> {
+    providers::copyq_history()
+}
+
+/// The `n`th item in copyq's first tab, 0 being the newest, or [`None`] past the end.
+///
+/// # Panics
+///
+/// If copyq isn't running. See [`try_copyq_history_item`].
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+#[must_use]
+pub fn copyq_history_item(n: usize) -> Option<String> {
+    try_copyq_history_item(n).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// The `n`th item in copyq's first tab, or [`None`] past the end.
+///
+/// # Errors
+///
+/// If copyq isn't installed or running, or it fails.
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+pub fn try_copyq_history_item(n: usize) -> Result<Option<String>> {
+    providers::copyq_history_item(n)
+}
+
+/// Add data of the given mime type to the top of copyq's first tab, without touching the clipboard.
+///
+/// ```no_run
+/// clipp::copyq_add("text/html", b"<b>saved</b>");
+/// ```
+///
+/// # Panics
+///
+/// If copyq isn't running. See [`try_copyq_add`].
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+pub fn copyq_add(mime: &str, data: &[u8]) {
+    try_copyq_add(mime, data).unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Add data of the given mime type to the top of copyq's first tab.
+///
+/// # Errors
+///
+/// If copyq isn't installed or running, or it fails.
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+pub fn try_copyq_add(mime: &str, data: &[u8]) -> Result<()> {
+    providers::copyq_add(mime, data)
+}
+
+/// The text in windows' clipboard history, the one win+v shows, newest first.
+/// Things without text, like images, are left out.
+///
+/// ```no_run
+/// for (i, text) in clipp::windows_history().iter().enumerate() {
+///     println!("{i}: {text}");
+/// }
+/// ```
+///
+/// # Panics
+///
+/// If the history is off, or reading it fails. See [`try_windows_history`].
+#[cfg(all(feature = "winrt", target_family = "windows"))]
+#[must_use]
+pub fn windows_history() -> Vec<String> {
+    try_windows_history().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// The text in windows' clipboard history, newest first.
+///
+/// # Errors
+///
+/// [`ClipError::Unsupported`] if the history is off (in settings, or by policy), or if reading it fails.
+#[cfg(all(feature = "winrt", target_family = "windows"))]
+pub fn try_windows_history() -> Result<Vec<String>> {
+    providers::windows_history()
+}
+
+/// Put the `n`th thing in [`windows_history`] back on the clipboard, in all its formats.
+/// False past the end, or if it was deleted since.
+///
+/// # Panics
+///
+/// If the history is off, or restoring fails. See [`try_windows_history_restore`].
+#[cfg(all(feature = "winrt", target_family = "windows"))]
+#[must_use]
+pub fn windows_history_restore(n: usize) -> bool {
+    try_windows_history_restore(n).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Put the `n`th thing in [`windows_history`] back on the clipboard. False past the end.
+///
+/// # Errors
+///
+/// [`ClipError::Unsupported`] if the history is off, or if restoring fails.
+#[cfg(all(feature = "winrt", target_family = "windows"))]
+pub fn try_windows_history_restore(n: usize) -> Result<bool> {
+    providers::windows_history_restore(n)
+}
+
+/// What the clipboard history manager keeps, newest first: cliphist's or clipman's on wlroots,
+/// or greenclip's on x11, whichever is installed first.
+/// So sway and hyprland (or i3) users get a history without clipp running another daemon.
+///
+/// ```no_run
+/// if let Some(last) = clipp::manager_history().get(1) {
+///     last.restore().unwrap();
+/// }
+/// ```
+///
+/// # Panics
+///
+/// If none are installed, or reading the history fails. See [`try_manager_history`].
+#[cfg(all(unix, not(target_os = "macos")))]
+#[must_use]
+pub fn manager_history() -> Vec<Stored> {
+    try_manager_history().unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// What cliphist, clipman, or greenclip keeps, newest first.
+///
+/// # Errors
+///
+/// [`ClipError::NoBackend`] if none are installed (or clipman hasn't saved anything), or if reading the history fails.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn try_manager_history() -> Result<Vec<Stored>> {
+    providers::stored()
+}
+
+/// Watch the given selection for changes, i.e. [`Selection::Primary`] to see what gets selected.
+///
+/// Ends if no clipboard is available.
+#[must_use]
+pub fn watch_from(sel: Selection) -> Watch {
+    match current() {
+        Ok(c) => c.watch_from(sel),
+        Err(_) => Watch::new(None, sel),
+    }
 }
@@ -1,12 +1,336 @@
 //! implements different clipboard types
+// the helpers go unused when backends are compiled out, or on windows and macos
+#![cfg_attr(
+    not(all(
+        unix,
+        not(any(target_os = "macos", target_os = "haiku")),
+        feature = "xclip",
+        feature = "xsel",
+        feature = "wayland",
+        feature = "wsl",
+        feature = "klipper",
+        feature = "copyq",
+        feature = "osc52",
+        feature = "tmux",
+        feature = "screen"
+    )),
+    allow(dead_code, unused_imports, unused_macros, unused_variables)
+)]
+use crate::{uri, Backend, ClipError, FileOp, Lazy, Result, Selection};
+mod base64;
+#[cfg(all(feature = "clippd", unix))]
+pub mod clippd;
+#[cfg(not(target_family = "wasm"))]
+mod commands;
+#[cfg(not(target_family = "wasm"))]
+pub use commands::Commands;
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+mod helper;
+#[cfg(all(feature = "klipper-native", unix, not(target_os = "macos")))]
+mod klipper;
+#[cfg(all(feature = "macos-native", target_os = "macos"))]
+mod macos;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod managers;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use managers::{stored, Stored};
+mod memory;
+pub use memory::Memory;
+#[cfg(all(feature = "osc52", unix, not(target_os = "macos")))]
+mod osc52;
+#[cfg(all(feature = "portal", unix, not(target_os = "macos")))]
+mod portal;
+#[cfg(all(feature = "wayland-native", unix, not(target_os = "macos")))]
+mod wayland;
+#[cfg(all(feature = "web", target_family = "wasm"))]
+mod web;
+#[cfg(all(feature = "web", target_family = "wasm"))]
+pub use web::{copy_async, paste_async};
+#[cfg(all(feature = "winrt", target_family = "windows"))]
+mod winrt;
+#[cfg(all(feature = "winrt", target_family = "windows"))]
+pub use winrt::{history as windows_history, restore as windows_history_restore};
+#[cfg(all(feature = "x11", unix, not(target_os = "macos")))]
+mod x11;
+#[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+use std::{
+    io::{BufRead, BufReader},
+    process::ChildStdout,
+};
 use std::{
     io::{Read, Write},
-    process::{Command, Stdio},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
 };
 
-pub trait Clipboard {
-    fn copy(text: &str);
-    fn paste() -> String;
+/// What a [`Provider::command`] does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    /// Takes the content on stdin.
+    Copy,
+    /// Prints the content on stdout.
+    Paste,
+}
+
+/// A clipboard. Install your own with [`set_provider`](crate::set_provider).
+///
+/// Only [`clear`](Provider::clear) is required, but nothing works without
+/// either [`command`](Provider::command) or [`copy_bytes`](Provider::copy_bytes) and [`paste_bytes`](Provider::paste_bytes).
+/// The rest have defaults built on those, which refuse anything that isn't text.
+///
+/// # Errors
+///
+/// Everything returns [`ClipError`]. [`ClipError::Unsupported`] is for things the clipboard can't do.
+#[allow(clippy::missing_errors_doc)]
+pub trait Provider: Send + Sync {
+    /// Empty `sel`.
+    fn clear(&self, sel: Selection) -> Result<()>;
+
+    /// The command that does `op`, for clipboards that are just a command.
+    /// [`None`] if there isn't one, or the clipboard doesn't have `sel`.
+    fn command(&self, _sel: Selection, _op: Op) -> Option<Command> {
+        None
+    }
+
+    /// Fix up what the paste command printed.
+    fn tidy(&self, out: Vec<u8>) -> Vec<u8> {
+        out
+    }
+
+    /// Copy raw bytes to `sel`.
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        self.command(sel, Op::Copy)
+            .ok_or(ClipError::Unsupported)?
+            .put(bytes)
+    }
+
+    /// Paste raw bytes from `sel`.
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        self.command(sel, Op::Paste)
+            .ok_or(ClipError::Unsupported)?
+            .eat()
+            .map(|o| self.tidy(o))
+    }
+
+    /// Paste raw bytes from `sel` onto the end of `buf`, so its allocation can be reused.
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        buf.extend(self.paste_bytes(sel)?);
+        Ok(())
+    }
+
+    /// Copy everything `r` reads to `sel`. Backends that take it on a command's stdin pipe it there as it's read;
+    /// others read all of it first.
+    fn copy_from_reader(&self, sel: Selection, r: &mut dyn Read) -> Result<()> {
+        let mut bytes = vec![];
+        r.read_to_end(&mut bytes).map_err(ClipError::Io)?;
+        self.copy_bytes(sel, &bytes)
+    }
+
+    /// Paste raw bytes from `sel` into `w`. Backends that print it from a command pipe it there as it comes.
+    fn paste_to_writer(&self, sel: Selection, w: &mut dyn Write) -> Result<()> {
+        w.write_all(&self.paste_bytes(sel)?).map_err(ClipError::Io)
+    }
+
+    /// Copy text to `sel`.
+    fn copy(&self, sel: Selection, text: &str) -> Result<()> {
+        self.copy_bytes(sel, text.as_bytes())
+    }
+
+    /// Paste text from `sel`.
+    fn paste(&self, sel: Selection) -> Result<String> {
+        String::from_utf8(self.paste_bytes(sel)?).map_err(ClipError::Utf8)
+    }
+
+    /// Copy data of the given mime type to `sel`.
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        if plain(mime) {
+            return self.copy_bytes(sel, bytes);
+        }
+        Err(ClipError::Unsupported)
+    }
+
+    /// The formats `sel` holds: mime types where there is one, i.e. `text/html`,
+    /// and the platform's own names otherwise, i.e. `UTF8_STRING`. Empty if it holds nothing.
+    fn formats(&self, _sel: Selection) -> Result<Vec<String>> {
+        Err(ClipError::Unsupported)
+    }
+
+    /// Paste data of the given mime type from `sel`. [`None`] if it doesn't hold that type.
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        if plain(mime) {
+            return self.paste_bytes(sel).map(Some);
+        }
+        Err(ClipError::Unsupported)
+    }
+
+    /// Copy a list of files to `sel`, as `text/uri-list`.
+    fn copy_files(&self, sel: Selection, paths: &[PathBuf]) -> Result<()> {
+        let list = uri::list(paths).map_err(ClipError::Io)?;
+        self.copy_mime(sel, "text/uri-list", list.as_bytes())
+    }
+
+    /// Paste a list of files from `sel`, from `text/uri-list`.
+    fn paste_files(&self, sel: Selection) -> Result<Vec<PathBuf>> {
+        Ok(self
+            .paste_mime(sel, "text/uri-list")?
+            .map(|l| uri::parse(&l))
+            .unwrap_or_default())
+    }
+
+    /// Copy a list of files to `sel`, for file managers to copy or move when they're pasted:
+    /// `text/uri-list`, with gnome's `x-special/gnome-copied-files` and kde's `application/x-kde-cutselection`.
+    /// Clipboards that hold one format get gnome's, to cut, and the uri list, to copy.
+    fn copy_files_op(&self, sel: Selection, paths: &[PathBuf], op: FileOp) -> Result<()> {
+        let list = uri::list(paths).map_err(ClipError::Io)?;
+        let gnome = uri::gnome(paths, op).map_err(ClipError::Io)?;
+        let kde: &[u8] = match op {
+            FileOp::Copy => b"0",
+            FileOp::Cut => b"1",
+        };
+        let formats: &[(&str, &[u8])] = &[
+            ("text/uri-list", list.as_bytes()),
+            ("x-special/gnome-copied-files", gnome.as_bytes()),
+            ("application/x-kde-cutselection", kde),
+        ];
+        match self.copy_formats(sel, formats) {
+            Err(ClipError::Unsupported) if op == FileOp::Cut => {
+                self.copy_mime(sel, "x-special/gnome-copied-files", gnome.as_bytes())
+            }
+            Err(ClipError::Unsupported) => self.copy_files(sel, paths),
+            r => r,
+        }
+    }
+
+    /// Paste a list of files from `sel`, and whether they were copied or cut.
+    fn paste_files_op(&self, sel: Selection) -> Result<(Vec<PathBuf>, FileOp)> {
+        let mime = |m| match self.paste_mime(sel, m) {
+            Err(ClipError::Unsupported) => Ok(None),
+            r => r,
+        };
+        if let Some(g) = mime("x-special/gnome-copied-files")? {
+            return Ok(uri::parse_gnome(&g));
+        }
+        let op = match mime("application/x-kde-cutselection")?.as_deref() {
+            Some(b"1") => FileOp::Cut,
+            _ => FileOp::Copy,
+        };
+        Ok((self.paste_files(sel)?, op))
+    }
+
+    /// Something that blocks until `sel` (probably) changed, if the clipboard can tell.
+    /// Otherwise [`watch`](crate::watch) polls. Backends that poll something cheaper than pasting do it `every` so often.
+    /// It ends soon after `stop` is set, and stops whatever it started, as that's when the watch is dropped.
+    fn changes(
+        &self,
+        _sel: Selection,
+        _every: Duration,
+        _stop: &Arc<AtomicBool>,
+    ) -> Option<Box<dyn Iterator<Item = ()> + Send>> {
+        None
+    }
+
+    /// How many bytes of `mime` `sel` holds, found without pasting it, where the backend can tell.
+    /// [`None`] if it can't, or there's none.
+    fn size(&self, _sel: Selection, _mime: &str) -> Result<Option<usize>> {
+        Ok(None)
+    }
+
+    /// Something that changes whenever what `sel` holds does:
+    /// a counter where the platform keeps one, and a hash of the text otherwise.
+    fn change_count(&self, sel: Selection) -> Result<u64> {
+        self.paste_bytes(sel).map(|b| crate::watch::hash(&b))
+    }
+
+    /// Put data of the given mime type on `sel` that's only made when something pastes it.
+    /// Only backends that serve `sel` from this process can.
+    fn copy_lazy(&self, _sel: Selection, _mime: &str, _data: Lazy) -> Result<()> {
+        Err(ClipError::Unsupported)
+    }
+
+    /// Put several representations of the same thing, by mime type, on `sel` at once.
+    fn copy_formats(&self, sel: Selection, formats: &[(&str, &[u8])]) -> Result<()> {
+        match formats {
+            [(mime, bytes)] => self.copy_mime(sel, mime, bytes),
+            _ => Err(ClipError::Unsupported),
+        }
+    }
+
+    /// Put several things on `sel` at once, each in several formats, by mime type, like finder does with files.
+    /// Clipboards that hold one thing can only take one.
+    fn copy_items(&self, sel: Selection, items: &[&[(&str, &[u8])]]) -> Result<()> {
+        match items {
+            [] => self.clear(sel),
+            [formats] => self.copy_formats(sel, formats),
+            _ => Err(ClipError::Unsupported),
+        }
+    }
+
+    /// Everything on `sel`, each thing in every format it's in.
+    /// Clipboards that hold one thing give it, in each of the [`formats`](Provider::formats).
+    /// The x11 targets that say things about the selection rather than hold it are left out,
+    /// as are formats that fail to paste, unless they all do.
+    fn paste_items(&self, sel: Selection) -> Result<Vec<Item>> {
+        let mut item = vec![];
+        let mut failed = None;
+        for mime in self.formats(sel)? {
+            if X11_META.contains(&&*mime) {
+                continue;
+            }
+            match self.paste_mime(sel, &mime) {
+                Ok(Some(data)) => item.push((mime, data)),
+                Ok(None) => {}
+                Err(e) => {
+                    event!(debug, "{mime} wouldn't paste: {e}");
+                    failed.get_or_insert(e);
+                }
+            }
+        }
+        match failed {
+            Some(e) if item.is_empty() => Err(e),
+            _ => Ok(if item.is_empty() { vec![] } else { vec![item] }),
+        }
+    }
+}
+
+/// x11 targets that aren't the data, but ways of asking for it or about it
+const X11_META: [&str; 8] = [
+    "TARGETS",
+    "TIMESTAMP",
+    "MULTIPLE",
+    "SAVE_TARGETS",
+    "DELETE",
+    "INSERT_SELECTION",
+    "INSERT_PROPERTY",
+    "LENGTH",
+];
+
+/// One thing on the clipboard, in each of the formats it's in: mime types and their data.
+pub type Item = Vec<(String, Vec<u8>)>;
+
+pub fn plain(mime: &str) -> bool {
+    matches!(
+        mime,
+        "text/plain" | "text/plain;charset=utf-8" | "UTF8_STRING"
+    )
+}
+
+/// for providers that can only hold text
+fn text(bytes: &[u8]) -> Result<&str> {
+    std::str::from_utf8(bytes)
+        .map_err(|_| ClipError::Utf8(String::from_utf8(bytes.to_vec()).unwrap_err()))
+}
+
+/// for providers that only have the one clipboard
+fn only_clipboard(sel: Selection) -> Result<()> {
+    match sel {
+        Selection::Clipboard => Ok(()),
+        Selection::Primary => Err(ClipError::Unsupported),
+    }
 }
 
 macro_rules! c {
@@ -22,210 +346,2366 @@ macro_rules! c {
     }
 }
 
+/// fails if the command did, with what it said on stderr
+pub fn check(status: ExitStatus, stderr: impl FnOnce() -> Vec<u8>) -> Result<()> {
+    if status.success() {
+        return Ok(());
+    }
+    Err(ClipError::Status {
+        status,
+        stderr: String::from_utf8_lossy(&stderr()).trim().to_owned(),
+    })
+}
+
+/// what a command says on stderr, read on the side.
+/// commands that fork into the background keep it open, so it's only waited for when they fail.
+struct Complaints(Option<mpsc::Receiver<Vec<u8>>>);
+
+impl Complaints {
+    fn hear(ch: &mut Child) -> Self {
+        Self(ch.stderr.take().map(|mut e| {
+            let (tx, rx) = mpsc::channel();
+            std::thread::spawn(move || {
+                let mut b = vec![];
+                _ = e.read_to_end(&mut b);
+                _ = tx.send(b);
+            });
+            rx
+        }))
+    }
+
+    fn said(self) -> Vec<u8> {
+        self.0
+            .and_then(|r| r.recv_timeout(Duration::from_millis(100)).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn spawn(c: &mut Command) -> Result<(Child, Complaints)> {
+    // not the arguments, which can be what's copied
+    let mut ch = match c.stderr(Stdio::piped()).spawn() {
+        Ok(ch) => ch,
+        Err(e) => {
+            event!(debug, "{:?} can't be run: {e}", c.get_program());
+            return Err(ClipError::Spawn(e));
+        }
+    };
+    event!(debug, "running {:?}, as {}", c.get_program(), ch.id());
+    let e = Complaints::hear(&mut ch);
+    Ok((ch, e))
+}
+
+/// how long commands get, in millis, by [`Op`]. [`u64::MAX`] is forever.
+static TIMEOUTS: [AtomicU64; 2] = [AtomicU64::new(5000), AtomicU64::new(5000)];
+
+fn slot(op: Op) -> &'static AtomicU64 {
+    match op {
+        Op::Copy => &TIMEOUTS[0],
+        Op::Paste => &TIMEOUTS[1],
+    }
+}
+
+/// the most that's copied or pasted at once, [`usize::MAX`] being no limit
+static MAX_SIZE: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+pub fn set_max_size(limit: Option<usize>) {
+    MAX_SIZE.store(limit.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// how much to read to know it's over the limit: one past it
+pub fn past_limit() -> u64 {
+    u64::try_from(MAX_SIZE.load(Ordering::Relaxed)).map_or(u64::MAX, |m| m.saturating_add(1))
+}
+
+/// [`ClipError::TooBig`] if `len` bytes is over the limit
+pub fn fits(len: usize) -> Result<()> {
+    let limit = MAX_SIZE.load(Ordering::Relaxed);
+    if len > limit {
+        return Err(ClipError::TooBig { limit });
+    }
+    Ok(())
+}
+
+pub fn set_timeout(op: Op, timeout: Option<Duration>) {
+    let ms = timeout.map_or(u64::MAX, |t| {
+        u64::try_from(t.as_millis()).unwrap_or(u64::MAX)
+    });
+    slot(op).store(ms, Ordering::Relaxed);
+}
+
+pub fn timeout(op: Op) -> Option<Duration> {
+    match slot(op).load(Ordering::Relaxed) {
+        u64::MAX => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+/// how many times to try opening the windows clipboard, and how long to wait after the first failure, in millis
+static TRIES: AtomicU64 = AtomicU64::new(10);
+static BACKOFF: AtomicU64 = AtomicU64::new(1);
+
+pub fn set_retries(tries: u32, backoff: Duration) {
+    TRIES.store(tries.into(), Ordering::Relaxed);
+    let ms = u64::try_from(backoff.as_millis()).unwrap_or(u64::MAX);
+    BACKOFF.store(ms, Ordering::Relaxed);
+}
+
+/// the wayland seat to use, by name. [`None`] is any of them.
+static SEAT: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn set_seat(seat: Option<&str>) {
+    *SEAT.lock().unwrap_or_else(PoisonError::into_inner) = seat.map(str::to_owned);
+}
+
+fn seat() -> Option<String> {
+    SEAT.lock().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// the mime types pasting text looks for first, in order. empty asks `CLIPP_PREFER`
+static PREFERRED: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+pub fn set_preferred(order: &[&str]) {
+    *PREFERRED.lock().unwrap_or_else(PoisonError::into_inner) =
+        order.iter().map(|&m| m.to_owned()).collect();
+}
+
+pub fn preferred() -> Vec<String> {
+    let set = PREFERRED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if !set.is_empty() {
+        return set;
+    }
+    std::env::var("CLIPP_PREFER")
+        .map(|p| {
+            p.split(',')
+                .map(str::trim)
+                .filter(|m| !m.is_empty())
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// the first of `preferred` that's in `formats`, unless the text comes first
+pub fn pick<'a>(preferred: &'a [String], formats: &[String]) -> Option<&'a str> {
+    preferred
+        .iter()
+        .find(|m| plain(m) || formats.contains(m))
+        .filter(|m| !plain(m))
+        .map(|m| &**m)
+}
+
+static PERSISTENT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_persistent(on: bool) {
+    PERSISTENT.store(on, Ordering::Relaxed);
+    #[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+    if !on {
+        *SHELL.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+fn persistent() -> bool {
+    PERSISTENT.load(Ordering::Relaxed)
+}
+
+/// wait for `ch`, killing it if it takes longer than `op` may
+fn wait(ch: Child, e: Complaints, op: Op) -> Result<()> {
+    #[cfg(feature = "log")]
+    let (id, start) = (ch.id(), Instant::now());
+    let r = wait_for(ch, timeout(op)).and_then(|s| check(s, || e.said()));
+    event!(debug, "{id} finished in {:?}: {r:?}", start.elapsed());
+    r
+}
+
+fn wait_for(mut ch: Child, timeout: Option<Duration>) -> Result<ExitStatus> {
+    let Some(t) = timeout else {
+        return ch.wait().map_err(ClipError::Io);
+    };
+    let deadline = Instant::now() + t;
+    loop {
+        if let Some(s) = ch.try_wait().map_err(ClipError::Io)? {
+            return Ok(s);
+        }
+        if Instant::now() > deadline {
+            _ = ch.kill();
+            _ = ch.wait();
+            return Err(ClipError::Timeout);
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+trait Run {
+    fn run(&mut self) -> Result<()>;
+}
+
+impl Run for Command {
+    fn run(&mut self) -> Result<()> {
+        let (ch, e) = spawn(self)?;
+        wait(ch, e, Op::Copy)
+    }
+}
+
 trait Eat {
-    fn eat(&mut self) -> String;
+    fn eat(&mut self) -> Result<Vec<u8>> {
+        let mut v = vec![];
+        self.eat_into(&mut v)?;
+        Ok(v)
+    }
+
+    fn eat_into(&mut self, buf: &mut Vec<u8>) -> Result<()>;
+
+    fn eat_to(&mut self, w: &mut dyn Write) -> Result<()>;
 }
 
 impl Eat for Command {
-    fn eat(&mut self) -> String {
-        let mut s = String::new();
-        self.stdout(Stdio::piped())
-            .spawn()
-            .expect("spawn ok")
-            .stdout
-            .take()
-            .unwrap()
-            .read_to_string(&mut s)
-            .expect("read ok");
-        s
+    fn eat_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        let (mut ch, e) = spawn(self.stdout(Stdio::piped()))?;
+        let out = ch.stdout.take().unwrap();
+        let max = past_limit();
+        // read on the side, so a stuck command can still be killed.
+        // stopping closes the pipe, which ends a command that's printing too much
+        std::thread::scope(|s| {
+            let r = s.spawn(move || out.take(max).read_to_end(buf));
+            let waited = wait(ch, e, Op::Paste);
+            let read = r.join().unwrap();
+            if let Ok(n) = read {
+                fits(n)?;
+            }
+            waited?;
+            read.map(drop).map_err(ClipError::Io)
+        })
+    }
+
+    fn eat_to(&mut self, w: &mut dyn Write) -> Result<()> {
+        let (mut ch, e) = spawn(self.stdout(Stdio::piped()))?;
+        let mut out = ch.stdout.take().unwrap();
+        // wait on the side, as `w` can't go to another thread
+        std::thread::scope(|s| {
+            let waited = s.spawn(move || wait(ch, e, Op::Paste));
+            let copied = std::io::copy(&mut out, w);
+            waited.join().unwrap()?;
+            copied.map(drop).map_err(ClipError::Io)
+        })
     }
 }
 
+/// [`Provider::paste_bytes_into`], reading the paste command straight into `buf`.
+/// only for providers that don't [`tidy`](Provider::tidy).
+fn eat_into(p: &(impl Provider + ?Sized), sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+    p.command(sel, Op::Paste)
+        .ok_or(ClipError::Unsupported)?
+        .eat_into(buf)
+}
+
+/// [`Provider::paste_to_writer`], piping the paste command into `w`. only for providers that don't tidy
+fn eat_to(p: &(impl Provider + ?Sized), sel: Selection, w: &mut dyn Write) -> Result<()> {
+    p.command(sel, Op::Paste)
+        .ok_or(ClipError::Unsupported)?
+        .eat_to(w)
+}
+
+/// [`Provider::copy_from_reader`], piping `r` into the copy command.
+/// only for providers that copy with nothing but the command
+fn put_from(p: &(impl Provider + ?Sized), sel: Selection, r: &mut dyn Read) -> Result<()> {
+    p.command(sel, Op::Copy)
+        .ok_or(ClipError::Unsupported)?
+        .put_from(r)
+}
+
 trait Put {
-    fn put(&mut self, s: impl AsRef<[u8]>);
+    fn put(&mut self, s: impl AsRef<[u8]>) -> Result<()>;
+
+    fn put_from(&mut self, r: &mut dyn Read) -> Result<()>;
 }
 
 impl Put for Command {
-    fn put(&mut self, s: impl AsRef<[u8]>) {
-        let mut ch = self.stdin(Stdio::piped()).spawn().expect("spawn ok");
-        ch.stdin
-            .take()
-            .unwrap()
-            .write_all(s.as_ref())
-            .expect("write ok");
-        ch.wait().expect("proc ok");
+    fn put(&mut self, s: impl AsRef<[u8]>) -> Result<()> {
+        let (mut ch, e) = spawn(self.stdin(Stdio::piped()))?;
+        let mut stdin = ch.stdin.take().unwrap();
+        let s = s.as_ref();
+        // write on the side, so a stuck command can still be killed
+        std::thread::scope(|sc| {
+            let w = sc.spawn(move || stdin.write_all(s));
+            wait(ch, e, Op::Copy)?;
+            w.join().unwrap().map_err(ClipError::Io)
+        })
+    }
+
+    fn put_from(&mut self, r: &mut dyn Read) -> Result<()> {
+        let (mut ch, e) = spawn(self.stdin(Stdio::piped()))?;
+        let stdin = ch.stdin.take().unwrap();
+        // wait on the side, as `r` can't go to another thread
+        std::thread::scope(|sc| {
+            let waited = sc.spawn(move || wait(ch, e, Op::Copy));
+            // moved in, so it's closed right after: the end of what's copied
+            let copied = std::io::copy(r, &mut { stdin });
+            waited.join().unwrap()?;
+            copied.map(drop).map_err(ClipError::Io)
+        })
     }
 }
 
 #[cfg(target_os = "macos")]
 pub struct PbCopy {}
 #[cfg(target_os = "macos")]
-impl Clipboard for PbCopy {
-    fn copy(text: &str) {
-        c!(pbcopy w).put(text)
+impl Provider for PbCopy {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        only_clipboard(sel).ok()?;
+        Some(match op {
+            Op::Copy => c!("pbcopy"),
+            Op::Paste => c!("pbpaste"),
+        })
     }
 
-    fn paste() -> String {
-        c!(pbcopy r).eat()
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        eat_into(self, sel, buf)
     }
-}
 
-pub struct XClip {}
-impl Clipboard for XClip {
-    fn copy(text: &str) {
-        c!("xclip" "-selection" "c").put(text);
+    fn paste_to_writer(&self, sel: Selection, w: &mut dyn Write) -> Result<()> {
+        eat_to(self, sel, w)
     }
 
-    fn paste() -> String {
-        c!("xclip" "-selection" "c" "-o") // xcclip is complainy
-            .stderr(Stdio::null())
-            .stdout(Stdio::null())
-            .eat()
+    fn copy_from_reader(&self, sel: Selection, r: &mut dyn Read) -> Result<()> {
+        put_from(self, sel, r)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        only_clipboard(sel)?;
+        c!("pbcopy").put("")
     }
 }
 
-pub struct XSel {}
-impl Clipboard for XSel {
-    fn copy(text: &str) {
-        c!("xsel" "-b" "-i").put(text);
+#[cfg(target_os = "haiku")]
+struct Haiku {}
+#[cfg(target_os = "haiku")]
+impl Provider for Haiku {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        only_clipboard(sel).ok()?;
+        let mut c = c!("clipboard");
+        c.arg(match op {
+            Op::Copy => "-i",
+            Op::Paste => "-o",
+        });
+        Some(c)
+    }
+
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        eat_into(self, sel, buf)
     }
 
-    fn paste() -> String {
-        c!("xsel" "-b" "-o").eat()
+    fn paste_to_writer(&self, sel: Selection, w: &mut dyn Write) -> Result<()> {
+        eat_to(self, sel, w)
+    }
+
+    fn copy_from_reader(&self, sel: Selection, r: &mut dyn Read) -> Result<()> {
+        put_from(self, sel, r)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        only_clipboard(sel)?;
+        c!("clipboard" "-r").run()
     }
 }
 
-struct Wayland {}
-impl Clipboard for Wayland {
-    fn copy(text: &str) {
-        match text {
-            "" => assert!(
-                c!("wl-copy" "-p" "--clear").status().unwrap().success(),
-                "wl-copy fail"
-            ),
-            s => c!("wl-copy" "-p").put(s),
+#[cfg(all(feature = "xclip", unix, not(target_os = "macos")))]
+#[derive(Default)]
+pub struct XClip {
+    /// instead of `DISPLAY`
+    display: Option<String>,
+}
+#[cfg(all(feature = "xclip", unix, not(target_os = "macos")))]
+impl XClip {
+    fn sel(&self, sel: Selection) -> Command {
+        let mut c = c!("xclip");
+        if let Some(d) = &self.display {
+            c.env("DISPLAY", d);
         }
+        c.arg("-selection").arg(match sel {
+            Selection::Clipboard => "c",
+            Selection::Primary => "p",
+        });
+        c
     }
 
-    fn paste() -> String {
-        c!("wl-paste" "-n" "-p").eat()
+    /// text from an owner that wouldn't give `UTF8_STRING`, which failed with `e`:
+    /// the best of its `TARGETS` that xclip didn't ask for
+    fn other_text(&self, sel: Selection, e: ClipError) -> Result<Vec<u8>> {
+        match best_text(&self.formats(sel)?) {
+            Some("STRING") => Ok(from_latin1(
+                &self.sel(sel).args(["-o", "-t", "STRING"]).eat()?,
+            )),
+            Some(t @ "text/plain;charset=utf-8") => self.sel(sel).args(["-o", "-t", t]).eat(),
+            // nobody owns it, or it has no text
+            _ => Err(e),
+        }
     }
 }
 
-struct Klipper {}
-impl Clipboard for Klipper {
-    fn copy(text: &str) {
-        c!("qdbus" "org.kde.klipper" "/klipper" "setClipboardContents").arg(text);
+/// what to ask an x11 owner for text in, best first
+#[cfg(any(all(feature = "xclip", unix, not(target_os = "macos")), test))]
+fn best_text(targets: &[String]) -> Option<&'static str> {
+    ["UTF8_STRING", "text/plain;charset=utf-8", "STRING"]
+        .into_iter()
+        .find(|t| targets.iter().any(|have| have == t))
+}
+
+/// `STRING` is latin 1
+#[cfg(any(all(feature = "xclip", unix, not(target_os = "macos")), test))]
+fn from_latin1(b: &[u8]) -> Vec<u8> {
+    b.iter()
+        .copied()
+        .map(char::from)
+        .collect::<String>()
+        .into_bytes()
+}
+
+#[cfg(all(feature = "xclip", unix, not(target_os = "macos")))]
+impl Provider for XClip {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        let mut c = self.sel(sel);
+        if op == Op::Paste {
+            c.arg("-o");
+        }
+        Some(c)
     }
 
-    fn paste() -> String {
-        let mut s = c!("qdbus" "org.kde.klipper" "/klipper" "getClipboardContents").eat();
-        assert!(s.ends_with('\n'));
-        s.truncate(s.len() - 1);
-        s
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        let mut b = vec![];
+        self.paste_bytes_into(sel, &mut b)?;
+        Ok(b)
     }
-}
 
-#[cfg(target_family = "windows")]
-struct Windows {}
-#[cfg(target_family = "windows")]
-impl Clipboard for Windows {
-    fn copy(text: &str) {
-        clipboard_win::set_clipboard_string(text).expect("set clip ok")
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        let at = buf.len();
+        match self
+            .sel(sel)
+            .args(["-o", "-t", "UTF8_STRING"])
+            .eat_into(buf)
+        {
+            // only ask for `TARGETS` when the owner won't give `UTF8_STRING`
+            Err(e @ ClipError::Status { .. }) => {
+                buf.truncate(at);
+                buf.extend(self.other_text(sel, e)?);
+                Ok(())
+            }
+            r => r,
+        }
+    }
+
+    fn paste_to_writer(&self, sel: Selection, w: &mut dyn Write) -> Result<()> {
+        match self.sel(sel).args(["-o", "-t", "UTF8_STRING"]).eat_to(w) {
+            Err(e @ ClipError::Status { .. }) => w
+                .write_all(&self.other_text(sel, e)?)
+                .map_err(ClipError::Io),
+            r => r,
+        }
+    }
+
+    fn copy_from_reader(&self, sel: Selection, r: &mut dyn Read) -> Result<()> {
+        put_from(self, sel, r)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        // xclip cant clear, so own an empty selection instead
+        self.copy_bytes(sel, &[])
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        self.sel(sel).args(["-t", mime]).put(bytes)
     }
 
-    fn paste() -> String {
-        clipboard_win::get_clipboard_string().expect("get clip ok")
+    fn formats(&self, sel: Selection) -> Result<Vec<String>> {
+        match self.sel(sel).args(["-o", "-t", "TARGETS"]).eat() {
+            // nobody owns the selection
+            Err(ClipError::Status { .. }) => Ok(vec![]),
+            t => Ok(lines(&t?)
+                .filter(|&t| t != "TARGETS")
+                .map(str::to_owned)
+                .collect()),
+        }
+    }
+
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        if plain(mime) {
+            return self.paste_bytes(sel).map(Some);
+        }
+        match self.sel(sel).args(["-o", "-t", mime]).eat() {
+            // it hasn't got `mime`, or nobody owns it: one process, not a `TARGETS` first
+            Err(ClipError::Status { .. }) => Ok(None),
+            b => b.map(Some),
+        }
+    }
+}
+
+fn lines(b: &[u8]) -> impl Iterator<Item = &str> {
+    b.split(|&b| b == b'\n')
+        .filter_map(|l| std::str::from_utf8(l).ok())
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+}
+
+#[cfg(all(feature = "xsel", unix, not(target_os = "macos")))]
+#[derive(Default)]
+pub struct XSel {
+    /// instead of `DISPLAY`
+    display: Option<String>,
+}
+#[cfg(all(feature = "xsel", unix, not(target_os = "macos")))]
+impl XSel {
+    fn sel(&self, sel: Selection) -> Command {
+        let mut c = c!("xsel");
+        if let Some(d) = &self.display {
+            c.env("DISPLAY", d);
+        }
+        c.arg(match sel {
+            Selection::Clipboard => "-b",
+            Selection::Primary => "-p",
+        });
+        c
     }
 }
 
-struct Wsl {}
+#[cfg(all(feature = "xsel", unix, not(target_os = "macos")))]
+impl Provider for XSel {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        let mut c = self.sel(sel);
+        c.arg(match op {
+            Op::Copy => "-i",
+            Op::Paste => "-o",
+        });
+        Some(c)
+    }
+
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        eat_into(self, sel, buf)
+    }
+
+    fn paste_to_writer(&self, sel: Selection, w: &mut dyn Write) -> Result<()> {
+        eat_to(self, sel, w)
+    }
+
+    fn copy_from_reader(&self, sel: Selection, r: &mut dyn Read) -> Result<()> {
+        put_from(self, sel, r)
+    }
 
-impl Clipboard for Wsl {
-    fn copy(text: &str) {
-        c!("clip.exe").put(text);
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.sel(sel).arg("-c").run()
     }
+}
 
-    fn paste() -> String {
-        let mut s = c!("powershell.exe" "-noprofile" "-command" "Get-Clipboard").eat();
-        s.truncate(s.len() - 2); // \r\n
-        s
+#[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+#[derive(Default)]
+struct Wayland {
+    /// instead of `WAYLAND_DISPLAY`
+    display: Option<String>,
+}
+#[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+impl Wayland {
+    fn sel(&self, program: &str, sel: Selection) -> Command {
+        let mut c = Command::new(program);
+        if let Some(d) = &self.display {
+            c.env("WAYLAND_DISPLAY", d);
+        }
+        if sel == Selection::Primary {
+            c.arg("-p");
+        }
+        if let Some(s) = seat() {
+            c.args(["--seat", &s]);
+        }
+        c
     }
 }
 
-pub type Board = (for<'a> fn(&'a str), fn() -> String);
+#[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+impl Provider for Wayland {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        Some(match op {
+            Op::Copy => self.sel("wl-copy", sel),
+            Op::Paste => {
+                let mut c = self.sel("wl-paste", sel);
+                c.arg("-n");
+                c
+            }
+        })
+    }
+
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        eat_into(self, sel, buf)
+    }
+
+    fn paste_to_writer(&self, sel: Selection, w: &mut dyn Write) -> Result<()> {
+        eat_to(self, sel, w)
+    }
+
+    fn copy_from_reader(&self, sel: Selection, r: &mut dyn Read) -> Result<()> {
+        put_from(self, sel, r)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.sel("wl-copy", sel).arg("--clear").run()
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        self.sel("wl-copy", sel).args(["--type", mime]).put(bytes)
+    }
+
+    fn changes(
+        &self,
+        sel: Selection,
+        _every: Duration,
+        stop: &Arc<AtomicBool>,
+    ) -> Option<Box<dyn Iterator<Item = ()> + Send>> {
+        let mut ch = self
+            .sel("wl-paste", sel)
+            .args(["--watch", "echo"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let out = BufReader::new(ch.stdout.take()?);
+        let ch = Arc::new(Mutex::new(ch));
+        let killed = Arc::clone(&ch);
+        // it blocks reading, so it's killed from elsewhere, which ends the reading
+        crate::watch::on_stop(Arc::clone(stop), move || kill(&killed));
+        Some(Box::new(Lines(ch, out)))
+    }
+
+    fn formats(&self, sel: Selection) -> Result<Vec<String>> {
+        match self.sel("wl-paste", sel).arg("--list-types").eat() {
+            // nothing is copied
+            Err(ClipError::Status { .. }) => Ok(vec![]),
+            t => Ok(lines(&t?).map(str::to_owned).collect()),
+        }
+    }
 
-fn get<T: Clipboard>() -> Board {
-    (T::copy, T::paste)
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        if !self.formats(sel)?.iter().any(|t| t == mime) {
+            return Ok(None);
+        }
+        self.sel("wl-paste", sel)
+            .args(["-n", "--type", mime])
+            .eat()
+            .map(Some)
+    }
 }
 
-fn has(c: &str) -> bool {
-    c!("which")
-        .arg(c)
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .expect("ok")
-        .success()
+/// a line from a watcher process per change. kills it on drop.
+#[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+struct Lines(Arc<Mutex<Child>>, BufReader<ChildStdout>);
+
+#[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+fn kill(ch: &Mutex<Child>) {
+    let mut ch = ch.lock().unwrap_or_else(PoisonError::into_inner);
+    _ = ch.kill();
+    _ = ch.wait();
 }
 
-fn wsl() -> bool {
-    if let Ok(s) = std::fs::read_to_string("/proc/version") {
-        if s.to_lowercase().contains("microsoft") {
-            return true;
+#[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+impl Iterator for Lines {
+    type Item = ();
+
+    fn next(&mut self) -> Option<()> {
+        let mut s = String::new();
+        match self.1.read_line(&mut s) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(()),
         }
     }
-    false
 }
 
-pub fn provide() -> Board {
-    #[cfg(target_family = "windows")]
-    return get::<Windows>();
-    #[cfg(target_os = "macos")]
-    return get::<PbCopy>();
-
-    if wsl() {
-        return get::<Wsl>();
-    }
-    assert!(std::env::var("DISPLAY").is_ok(), "no clipboard available");
-    if std::env::var("WAYLAND_DISPLAY").is_ok() && has("wl-copy") {
-        get::<Wayland>()
-    } else if has("xsel") {
-        get::<XSel>()
-    } else if has("xclip") {
-        get::<XClip>()
-    } else if has("klipper") && has("qdbus") {
-        get::<Klipper>()
-    } else {
-        panic!("no clipboard available");
+#[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+impl Drop for Lines {
+    fn drop(&mut self) {
+        kill(&self.0);
     }
 }
 
-#[test]
-fn test() {
-    macro_rules! test {
-        ($clipboard:ty) => {
-            <$clipboard>::copy("text");
-            assert_eq!(<$clipboard>::paste(), "text");
-            <$clipboard>::copy("");
-        };
+/// through qdbus, without `klipper-native`
+#[cfg(all(
+    feature = "klipper",
+    not(feature = "klipper-native"),
+    unix,
+    not(target_os = "macos")
+))]
+struct Klipper {}
+#[cfg(all(
+    feature = "klipper",
+    not(feature = "klipper-native"),
+    unix,
+    not(target_os = "macos")
+))]
+impl Klipper {
+    /// one by one, as the menu comes back a line per entry, and entries can have lines of their own
+    fn history(&self) -> Result<Vec<String>> {
+        let mut all = vec![];
+        while let Some(s) = self.history_item(all.len())? {
+            all.push(s);
+        }
+        Ok(all)
     }
-    #[cfg(target_os = "macos")]
-    test!(PbCopy);
-    #[cfg(target_os = "linux")]
-    test!(XClip);
-    #[cfg(target_os = "linux")]
-    test!(XSel);
-    #[cfg(target_os = "linux")]
-    if std::env::var("WAYLAND_DISPLAY").is_ok() {
-        test!(Wayland);
+
+    /// klipper gives back nothing past the end
+    #[allow(clippy::unused_self)]
+    fn history_item(&self, n: usize) -> Result<Option<String>> {
+        let mut s = c!("qdbus" "org.kde.klipper" "/klipper" "getClipboardHistoryItem")
+            .arg(n.to_string())
+            .eat()?;
+        if s.ends_with(b"\n") {
+            s.truncate(s.len() - 1);
+        }
+        let s = String::from_utf8(s).map_err(ClipError::Utf8)?;
+        Ok((!s.is_empty()).then_some(s))
     }
-    #[cfg(target_os = "linux")]
-    test!(Klipper);
-    #[cfg(target_family = "windows")]
-    test!(Windows);
-    if wsl() {
-        #[cfg(target_os = "linux")]
-        test!(Wsl);
+}
+#[cfg(all(
+    feature = "klipper",
+    not(feature = "klipper-native"),
+    unix,
+    not(target_os = "macos")
+))]
+impl Provider for Klipper {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        only_clipboard(sel)?;
+        c!("qdbus" "org.kde.klipper" "/klipper" "setClipboardContents")
+            .arg(text(bytes)?)
+            .stdout(Stdio::null())
+            .run()
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        only_clipboard(sel)?;
+        let mut s = c!("qdbus" "org.kde.klipper" "/klipper" "getClipboardContents").eat()?;
+        if s.ends_with(b"\n") {
+            s.truncate(s.len() - 1);
+        }
+        Ok(s)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        only_clipboard(sel)?;
+        c!("qdbus" "org.kde.klipper" "/klipper" "clearClipboardContents")
+            .stdout(Stdio::null())
+            .run()
+    }
+}
+
+#[cfg(all(target_family = "windows", feature = "windows-native"))]
+#[allow(clippy::needless_pass_by_value)]
+fn os(e: clipboard_win::ErrorCode) -> ClipError {
+    ClipError::Io(std::io::Error::from_raw_os_error(e.raw_code()))
+}
+
+/// open the clipboard, waiting out whoever has it open now
+#[cfg(all(target_family = "windows", feature = "windows-native"))]
+fn open() -> Result<clipboard_win::Clipboard> {
+    let mut wait = Duration::from_millis(BACKOFF.load(Ordering::Relaxed));
+    for _ in 1..TRIES.load(Ordering::Relaxed) {
+        if let Ok(c) = clipboard_win::Clipboard::new() {
+            return Ok(c);
+        }
+        std::thread::sleep(wait);
+        wait = wait.saturating_mul(2);
+    }
+    clipboard_win::Clipboard::new().map_err(os)
+}
+
+#[cfg(all(target_family = "windows", feature = "windows-native"))]
+struct Windows {}
+#[cfg(all(target_family = "windows", feature = "windows-native"))]
+impl Provider for Windows {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        self.copy(sel, text(bytes)?)
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        self.paste(sel).map(String::into_bytes)
+    }
+
+    fn copy(&self, sel: Selection, text: &str) -> Result<()> {
+        only_clipboard(sel)?;
+        let _clip = open()?;
+        clipboard_win::set(clipboard_win::formats::Unicode, text).map_err(os)
+    }
+
+    fn paste(&self, sel: Selection) -> Result<String> {
+        only_clipboard(sel)?;
+        let _clip = open()?;
+        clipboard_win::get(clipboard_win::formats::Unicode).map_err(os)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        only_clipboard(sel)?;
+        let _clip = open()?;
+        clipboard_win::empty().map_err(os)
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        if plain(mime) {
+            return self.copy_bytes(sel, bytes);
+        }
+        self.copy_formats(sel, &[(mime, bytes)])
+    }
+
+    fn copy_files(&self, sel: Selection, paths: &[PathBuf]) -> Result<()> {
+        only_clipboard(sel)?;
+        let paths = Self::file_list(paths)?;
+        let _clip = open()?;
+        clipboard_win::raw::empty().map_err(os)?;
+        clipboard_win::raw::set_file_list(&paths).map_err(os)
+    }
+
+    fn paste_files(&self, sel: Selection) -> Result<Vec<PathBuf>> {
+        only_clipboard(sel)?;
+        let _clip = open()?;
+        if !clipboard_win::is_format_avail(clipboard_win::formats::CF_HDROP) {
+            return Ok(vec![]);
+        }
+        clipboard_win::get(clipboard_win::formats::FileList).map_err(os)
+    }
+
+    fn copy_files_op(&self, sel: Selection, paths: &[PathBuf], op: FileOp) -> Result<()> {
+        use clipboard_win::raw;
+        only_clipboard(sel)?;
+        let paths = Self::file_list(paths)?;
+        // what explorer puts there: DROPEFFECT_COPY | DROPEFFECT_LINK, or DROPEFFECT_MOVE
+        let effect: u32 = match op {
+            FileOp::Copy => 5,
+            FileOp::Cut => 2,
+        };
+        let f = Self::format(DROP_EFFECT)?;
+        // both while it's open, so nothing pastes the files without the effect in between
+        let _clip = open()?;
+        raw::empty().map_err(os)?;
+        raw::set_file_list(&paths).map_err(os)?;
+        raw::set_without_clear(f, &effect.to_le_bytes()).map_err(os)
+    }
+
+    fn paste_files_op(&self, sel: Selection) -> Result<(Vec<PathBuf>, FileOp)> {
+        let paths = self.paste_files(sel)?;
+        let f = Self::format(DROP_EFFECT)?;
+        let _clip = open()?;
+        if !clipboard_win::is_format_avail(f) {
+            return Ok((paths, FileOp::Copy));
+        }
+        let effect: Vec<u8> = clipboard_win::get(clipboard_win::formats::RawData(f)).map_err(os)?;
+        let cut = effect
+            .first_chunk()
+            .is_some_and(|&e| u32::from_le_bytes(e) & 2 != 0);
+        Ok((paths, if cut { FileOp::Cut } else { FileOp::Copy }))
+    }
+
+    fn copy_formats(&self, sel: Selection, formats: &[(&str, &[u8])]) -> Result<()> {
+        use clipboard_win::raw;
+        only_clipboard(sel)?;
+        let _clip = open()?;
+        raw::empty().map_err(os)?;
+        // set_string empties the clipboard, so it goes first
+        if let Some((_, t)) = formats.iter().find(|(m, _)| plain(m)) {
+            raw::set_string(text(t)?).map_err(os)?;
+        }
+        for &(mime, bytes) in formats.iter().filter(|(m, _)| !plain(m)) {
+            let f = Self::format(mime)?;
+            match mime {
+                "text/html" => raw::set_without_clear(f, cf_html(text(bytes)?).as_bytes()),
+                "image/bmp" => {
+                    raw::set_without_clear(f, to_dib(bytes).ok_or(ClipError::Unsupported)?)
+                }
+                _ => raw::set_without_clear(f, bytes),
+            }
+            .map_err(os)?;
+        }
+        // so paint and older programs can paste it too; windows makes CF_DIB from this one
+        #[cfg(feature = "image")]
+        if !formats.iter().any(|(m, _)| *m == "image/bmp") {
+            if let Some(dib) =
+                formats
+                    .iter()
+                    .find(|(m, _)| *m == "image/png")
+                    .and_then(|(_, png)| {
+                        image::load_from_memory_with_format(png, image::ImageFormat::Png).ok()
+                    })
+            {
+                raw::set_without_clear(
+                    clipboard_win::formats::CF_DIBV5,
+                    &to_dibv5(&dib.to_rgba8()),
+                )
+                .map_err(os)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn changes(
+        &self,
+        sel: Selection,
+        _every: Duration,
+        stop: &Arc<AtomicBool>,
+    ) -> Option<Box<dyn Iterator<Item = ()> + Send>> {
+        only_clipboard(sel).ok()?;
+        let (tx, rx) = mpsc::sync_channel(1);
+        let (ok, started) = mpsc::channel();
+        let stop = Arc::clone(stop);
+        // the monitor's window can't leave the thread that made it, so that thread pumps its messages
+        std::thread::Builder::new()
+            .name("clipp clipboard listener".into())
+            .spawn(move || {
+                let Ok(mut m) = clipboard_win::Monitor::new() else {
+                    _ = ok.send(false);
+                    return;
+                };
+                _ = ok.send(true);
+                // which, dropped, wakes it up to end
+                let shutdown = m.shutdown_channel();
+                crate::watch::on_stop(stop, move || drop(shutdown));
+                while let Ok(true) = m.recv() {
+                    // a change that's already waiting covers this one
+                    if let Err(mpsc::TrySendError::Disconnected(())) = tx.try_send(()) {
+                        break;
+                    }
+                }
+            })
+            .ok()?;
+        started.recv().ok()?.then_some(())?;
+        Some(Box::new(rx.into_iter()))
+    }
+
+    fn size(&self, sel: Selection, mime: &str) -> Result<Option<usize>> {
+        only_clipboard(sel)?;
+        let f = if plain(mime) {
+            clipboard_win::formats::CF_UNICODETEXT
+        } else {
+            Self::format(mime)?
+        };
+        let _clip = open()?;
+        // GlobalSize, which for text is utf16, and its nul
+        Ok(clipboard_win::raw::size(f).map(std::num::NonZeroUsize::get))
+    }
+
+    fn change_count(&self, sel: Selection) -> Result<u64> {
+        only_clipboard(sel)?;
+        // zero if we may not look
+        Ok(clipboard_win::raw::seq_num().map_or(0, |n| n.get().into()))
+    }
+
+    fn formats(&self, sel: Selection) -> Result<Vec<String>> {
+        only_clipboard(sel)?;
+        let _clip = open()?;
+        let mut all: Vec<String> = clipboard_win::raw::EnumFormats::new()
+            .filter_map(clipboard_win::raw::format_name_big)
+            .map(|n| {
+                match &*n {
+                    "CF_UNICODETEXT" => "text/plain",
+                    "HTML Format" => "text/html",
+                    "PNG" => "image/png",
+                    "CF_DIB" | "CF_DIBV5" => "image/bmp",
+                    "Rich Text Format" => "text/rtf",
+                    _ => return n,
+                }
+                .to_owned()
+            })
+            .collect();
+        // a bitmap can be pasted as png
+        #[cfg(feature = "image")]
+        if all.iter().any(|f| f == "image/bmp") && !all.iter().any(|f| f == "image/png") {
+            all.push("image/png".to_owned());
+        }
+        let mut seen = std::collections::HashSet::new();
+        all.retain(|f| seen.insert(f.clone()));
+        Ok(all)
+    }
+
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        if plain(mime) {
+            return self.paste_bytes(sel).map(Some);
+        }
+        only_clipboard(sel)?;
+        let f = Self::format(mime)?;
+        match mime {
+            "image/bmp" => return Ok(Self::dib()?.as_deref().and_then(from_dib)),
+            #[cfg(feature = "image")]
+            "image/png" if !clipboard_win::is_format_avail(f) => {
+                return Ok(Self::dib()?.as_deref().and_then(png_from_dib));
+            }
+            _ => {}
+        }
+        let _clip = open()?;
+        if !clipboard_win::is_format_avail(f) {
+            return Ok(None);
+        }
+        let data: Vec<u8> = clipboard_win::get(clipboard_win::formats::RawData(f)).map_err(os)?;
+        if mime == "text/html" {
+            if let Some(html) = from_cf_html(&data) {
+                return Ok(Some(html.to_vec()));
+            }
+        }
+        Ok(Some(data))
+    }
+}
+
+/// `CFSTR_PREFERREDDROPEFFECT`, which says if files were cut
+#[cfg(all(target_family = "windows", feature = "windows-native"))]
+const DROP_EFFECT: &str = "Preferred DropEffect";
+
+#[cfg(all(target_family = "windows", feature = "windows-native"))]
+impl Windows {
+    /// `paths`, made absolute, as `set_file_list` takes them
+    fn file_list(paths: &[PathBuf]) -> Result<Vec<String>> {
+        paths
+            .iter()
+            .map(|p| {
+                let p = std::path::absolute(p).map_err(ClipError::Io)?;
+                p.into_os_string()
+                    .into_string()
+                    .map_err(|_| ClipError::Io(std::io::ErrorKind::InvalidFilename.into()))
+            })
+            .collect()
+    }
+
+    /// registered clipboard format for a mime type
+    fn format(mime: &str) -> Result<u32> {
+        let name = match mime {
+            // windows turns it into the other bitmap formats as asked
+            "image/bmp" => return Ok(clipboard_win::formats::CF_DIB),
+            // what browsers and office use
+            "image/png" => "PNG",
+            "text/html" => "HTML Format",
+            "text/rtf" => "Rich Text Format",
+            m => m,
+        };
+        clipboard_win::register_format(name)
+            .map(std::num::NonZeroU32::get)
+            .ok_or_else(|| os(clipboard_win::ErrorCode::last_system()))
+    }
+
+    /// the bitmap on the clipboard: `CF_DIBV5`, which keeps alpha, or `CF_DIB`
+    fn dib() -> Result<Option<Vec<u8>>> {
+        use clipboard_win::formats::{RawData, CF_DIB, CF_DIBV5};
+        let _clip = open()?;
+        for f in [CF_DIBV5, CF_DIB] {
+            if clipboard_win::is_format_avail(f) {
+                return clipboard_win::get(RawData(f)).map(Some).map_err(os);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// wraps html in the windows `HTML Format` header
+#[cfg(any(all(target_family = "windows", feature = "windows-native"), test))]
+fn cf_html(html: &str) -> String {
+    const HEADER: usize = "Version:0.9\r\nStartHTML:0000000000\r\nEndHTML:0000000000\r\nStartFragment:0000000000\r\nEndFragment:0000000000\r\n".len();
+    const PRE: &str = "<html><body>\r\n<!--StartFragment-->";
+    const POST: &str = "<!--EndFragment-->\r\n</body></html>";
+    let start_fragment = HEADER + PRE.len();
+    let end_fragment = start_fragment + html.len();
+    let end = end_fragment + POST.len();
+    format!("Version:0.9\r\nStartHTML:{HEADER:010}\r\nEndHTML:{end:010}\r\nStartFragment:{start_fragment:010}\r\nEndFragment:{end_fragment:010}\r\n{PRE}{html}{POST}")
+}
+
+/// a bmp file without its file header, which is how windows keeps `CF_DIB`
+#[cfg(any(all(target_family = "windows", feature = "windows-native"), test))]
+fn to_dib(bmp: &[u8]) -> Option<&[u8]> {
+    bmp.strip_prefix(b"BM")?.get(12..)
+}
+
+/// a `CF_DIB` with the file header put back, so it's a bmp file.
+/// [`None`] for the old `BITMAPCOREHEADER`, that nothing puts there anymore
+#[cfg(any(all(target_family = "windows", feature = "windows-native"), test))]
+fn from_dib(dib: &[u8]) -> Option<Vec<u8>> {
+    let u32_at = |i: usize| Some(u32::from_le_bytes(dib.get(i..i + 4)?.try_into().ok()?));
+    let header = u32_at(0)?;
+    if header < 40 {
+        return None;
+    }
+    let bits = u16::from_le_bytes(dib.get(14..16)?.try_into().ok()?);
+    let used = u32_at(32)?;
+    let colors = match used {
+        0 if bits <= 8 => 1 << bits,
+        n => n,
+    };
+    // the masks come after the header for BI_BITFIELDS, unless they're in it
+    let masks = if header == 40 && u32_at(16)? == 3 {
+        12
+    } else {
+        0
+    };
+    // all of it read off the clipboard, so it can be anything
+    let offset = colors
+        .checked_mul(4)?
+        .checked_add(14 + masks)?
+        .checked_add(header)?;
+    let size = u32::try_from(14 + dib.len()).ok()?;
+    let mut bmp = Vec::with_capacity(14 + dib.len());
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&size.to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&offset.to_le_bytes());
+    bmp.extend_from_slice(dib);
+    Some(bmp)
+}
+
+/// a `CF_DIBV5` of `img`: 32 bit, bottom up, with an alpha mask
+#[cfg(all(
+    feature = "image",
+    any(all(target_family = "windows", feature = "windows-native"), test)
+))]
+fn to_dibv5(img: &image::RgbaImage) -> Vec<u8> {
+    const HEADER: u32 = 124;
+    let (w, h) = img.dimensions();
+    let mut dib = Vec::with_capacity(HEADER as usize + img.len());
+    for n in [HEADER, w, h] {
+        dib.extend_from_slice(&n.to_le_bytes());
+    }
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&32u16.to_le_bytes());
+    // BI_BITFIELDS, the size, resolutions, and palette
+    for n in [3, u32::try_from(img.len()).unwrap_or(0), 0, 0, 0, 0] {
+        dib.extend_from_slice(&n.to_le_bytes());
+    }
+    // the masks, and LCS_sRGB
+    for n in [
+        0x00ff_0000u32,
+        0xff00,
+        0xff,
+        0xff00_0000,
+        u32::from_be_bytes(*b"sRGB"),
+    ] {
+        dib.extend_from_slice(&n.to_le_bytes());
+    }
+    // the endpoints and gamma go unused with sRGB; then LCS_GM_IMAGES, and no profile
+    dib.resize(dib.len() + 36 + 12, 0);
+    for n in [4u32, 0, 0, 0] {
+        dib.extend_from_slice(&n.to_le_bytes());
+    }
+    debug_assert_eq!(dib.len(), HEADER as usize);
+    for row in img.rows().rev() {
+        for &image::Rgba([r, g, b, a]) in row {
+            dib.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    dib
+}
+
+/// a `CF_DIB` or `CF_DIBV5` as png
+#[cfg(all(
+    feature = "image",
+    any(all(target_family = "windows", feature = "windows-native"), test)
+))]
+fn png_from_dib(dib: &[u8]) -> Option<Vec<u8>> {
+    let img = image::load_from_memory_with_format(&from_dib(dib)?, image::ImageFormat::Bmp).ok()?;
+    let mut png = std::io::Cursor::new(vec![]);
+    img.write_to(&mut png, image::ImageFormat::Png).ok()?;
+    Some(png.into_inner())
+}
+
+/// the html document in a windows `HTML Format` blob
+#[cfg(any(all(target_family = "windows", feature = "windows-native"), test))]
+fn from_cf_html(data: &[u8]) -> Option<&[u8]> {
+    let offset = |key: &str| {
+        lines(data)
+            .take_while(|l| !l.starts_with('<'))
+            .find_map(|l| {
+                l.strip_prefix(key)?
+                    .strip_prefix(':')?
+                    .parse::<usize>()
+                    .ok()
+            })
+    };
+    let (start, end) = offset("StartHTML")
+        .zip(offset("EndHTML"))
+        .or_else(|| offset("StartFragment").zip(offset("EndFragment")))?;
+    data.get(start..end.min(data.len()))
+}
+
+#[cfg(all(feature = "tmux", unix, not(target_os = "macos")))]
+struct Tmux {}
+#[cfg(all(feature = "tmux", unix, not(target_os = "macos")))]
+impl Tmux {
+    fn buffer(cmd: &str) -> Command {
+        let mut c = c!("tmux");
+        c.args([cmd, "-"]);
+        c
+    }
+}
+#[cfg(all(feature = "tmux", unix, not(target_os = "macos")))]
+impl Provider for Tmux {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        only_clipboard(sel).ok()?;
+        Some(match op {
+            Op::Copy => Self::buffer("load-buffer"),
+            Op::Paste => Self::buffer("save-buffer"),
+        })
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        match self
+            .command(sel, Op::Paste)
+            .ok_or(ClipError::Unsupported)?
+            .eat()
+        {
+            // there are no buffers
+            Err(ClipError::Status { .. }) => Ok(vec![]),
+            b => b,
+        }
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        only_clipboard(sel)?;
+        c!("tmux" "delete-buffer").run()
+    }
+}
+
+#[cfg(all(feature = "screen", unix, not(target_os = "macos")))]
+struct Screen {}
+#[cfg(all(feature = "screen", unix, not(target_os = "macos")))]
+impl Screen {
+    /// screen only moves its buffer through files, which go in a directory only this user can get into,
+    /// made fresh, as anyone could have put something where they'd go in the shared one
+    fn dir() -> Result<PathBuf> {
+        use std::os::unix::fs::DirBuilderExt;
+        static N: AtomicU64 = AtomicU64::new(0);
+        loop {
+            let n = N.fetch_add(1, Ordering::Relaxed);
+            let d = std::env::temp_dir().join(format!("clipp-screen-{}-{n}", std::process::id()));
+            match std::fs::DirBuilder::new().mode(0o700).create(&d) {
+                Ok(()) => return Ok(d),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(ClipError::Io(e)),
+            }
+        }
+    }
+
+    /// the buffer, through a file in `d`
+    fn writebuf(d: &Path) -> Result<Vec<u8>> {
+        let f = d.join("out");
+        Self::x("writebuf", &f)?;
+        // -X doesnt wait for the session to write it
+        let deadline = Instant::now() + Duration::from_secs(1);
+        loop {
+            match std::fs::read(&f) {
+                Ok(b) => return Ok(b),
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(ClipError::Io(e)),
+                Err(_) if Instant::now() > deadline => return Ok(vec![]),
+                Err(_) => std::thread::sleep(Duration::from_millis(10)),
+            }
+        }
+    }
+
+    fn x(cmd: &str, file: &Path) -> Result<()> {
+        c!("screen")
+            .args(["-X", cmd])
+            .arg(file)
+            .stdout(Stdio::null())
+            .run()
+    }
+}
+#[cfg(all(feature = "screen", unix, not(target_os = "macos")))]
+impl Provider for Screen {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        only_clipboard(sel)?;
+        let d = Self::dir()?;
+        let f = d.join("in");
+        let r = std::fs::write(&f, bytes)
+            .map_err(ClipError::Io)
+            .and_then(|()| Self::x("readbuf", &f))
+            // the session reads it later, but does what it's told in order,
+            // so once the buffer comes back out, it's been read, and can go
+            .and_then(|()| Self::writebuf(&d).map(drop));
+        _ = std::fs::remove_dir_all(&d);
+        r
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        only_clipboard(sel)?;
+        let d = Self::dir()?;
+        let b = Self::writebuf(&d);
+        _ = std::fs::remove_dir_all(&d);
+        b
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.copy_bytes(sel, &[])
+    }
+}
+
+/// lemonade's client, which talks to the server on the machine with the clipboard
+#[cfg(all(feature = "lemonade", not(target_family = "wasm")))]
+struct Lemonade {}
+#[cfg(all(feature = "lemonade", not(target_family = "wasm")))]
+impl Provider for Lemonade {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        only_clipboard(sel).ok()?;
+        let mut c = c!("lemonade");
+        c.arg(match op {
+            Op::Copy => "copy",
+            Op::Paste => "paste",
+        });
+        Some(c)
+    }
+
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        eat_into(self, sel, buf)
+    }
+
+    fn paste_to_writer(&self, sel: Selection, w: &mut dyn Write) -> Result<()> {
+        eat_to(self, sel, w)
+    }
+
+    fn copy_from_reader(&self, sel: Selection, r: &mut dyn Read) -> Result<()> {
+        put_from(self, sel, r)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.command(sel, Op::Copy)
+            .ok_or(ClipError::Unsupported)?
+            .put([])
+    }
+}
+
+/// the clipper daemon copies whatever's sent to it, and can't be asked for it back
+#[cfg(all(feature = "clipper", not(target_family = "wasm")))]
+struct Clipper {}
+#[cfg(all(feature = "clipper", not(target_family = "wasm")))]
+impl Clipper {
+    /// where it listens by default. over ssh, port 8377 is forwarded to it instead
+    #[cfg(unix)]
+    fn socket() -> Option<PathBuf> {
+        let s = PathBuf::from(std::env::var_os("HOME")?).join(".clipper.sock");
+        s.exists().then_some(s)
+    }
+
+    fn send(bytes: &[u8]) -> Result<()> {
+        #[cfg(unix)]
+        if let Some(s) = Self::socket() {
+            let mut s = std::os::unix::net::UnixStream::connect(s).map_err(ClipError::Io)?;
+            return s.write_all(bytes).map_err(ClipError::Io);
+        }
+        let mut s = std::net::TcpStream::connect(("127.0.0.1", 8377)).map_err(ClipError::Io)?;
+        s.write_all(bytes).map_err(ClipError::Io)
+    }
+}
+#[cfg(all(feature = "clipper", not(target_family = "wasm")))]
+impl Provider for Clipper {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        only_clipboard(sel)?;
+        Self::send(bytes)
+    }
+
+    fn paste_bytes(&self, _sel: Selection) -> Result<Vec<u8>> {
+        Err(ClipError::Unsupported)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.copy_bytes(sel, &[])
+    }
+}
+
+/// a powershell command, quietly
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+fn powershell(script: &str) -> Command {
+    let mut c = c!("powershell.exe");
+    c.args(["-noprofile", "-noninteractive", "-command", script]);
+    c
+}
+
+/// the console codepage is not utf8, so these read and write through utf8 streams
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+const PS_COPY: &str = "$i = New-Object IO.StreamReader([Console]::OpenStandardInput(), [Text.Encoding]::UTF8); Set-Clipboard -Value $i.ReadToEnd()";
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+const PS_PASTE: &str = "$o = New-Object IO.StreamWriter([Console]::OpenStandardOutput(), (New-Object Text.UTF8Encoding $false)); $o.Write((Get-Clipboard -Raw)); $o.Flush()";
+
+/// the powershell kept around by [`set_persistent`]
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+static SHELL: Mutex<Option<helper::Helper>> = Mutex::new(None);
+
+/// run `script` in the kept powershell, starting it if need be
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+fn kept(script: &str, op: Op) -> Result<Vec<String>> {
+    let mut shell = SHELL.lock().unwrap_or_else(PoisonError::into_inner);
+    let h = match &mut *shell {
+        Some(h) => h,
+        None => shell.insert(helper::Helper::start(&mut powershell("-"))?),
+    };
+    let r = h.run(script, op);
+    if r.is_err() {
+        // start over next time
+        *shell = None;
+    }
+    r
+}
+
+/// through base64, as stdin is taken and the console codepage is not utf8
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+fn kept_copy(bytes: &[u8]) -> Result<()> {
+    const PRE: &str =
+        "Set-Clipboard -Value ([Text.Encoding]::UTF8.GetString([Convert]::FromBase64String('";
+    const POST: &str = "')))";
+    let mut b64 = base64::encode(bytes);
+    // made to size, so growing it leaves no copies behind
+    let mut script = String::with_capacity(PRE.len() + b64.len() + POST.len());
+    script.push_str(PRE);
+    script.push_str(&b64);
+    script.push_str(POST);
+    let r = kept(&script, Op::Copy).map(drop);
+    // it might be a secret
+    wipe(&mut b64);
+    wipe(&mut script);
+    r
+}
+
+#[cfg(feature = "secrecy")]
+fn wipe(s: &mut String) {
+    secrecy::zeroize::Zeroize::zeroize(s);
+}
+
+#[cfg(not(feature = "secrecy"))]
+fn wipe(_: &mut String) {}
+
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+fn kept_paste() -> Result<Vec<u8>> {
+    let out = kept(
+        "[Convert]::ToBase64String([Text.Encoding]::UTF8.GetBytes([string](Get-Clipboard -Raw)))",
+        Op::Paste,
+    )?;
+    base64::decode(out.concat().trim()).ok_or_else(|| {
+        ClipError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "powershell printed something that isn't base64",
+        ))
+    })
+}
+
+/// the windows clipboard, through powershell.exe: on windows without clipboard-win, and under wsl,
+/// where `clip.exe` takes the OEM codepage and mangles utf8
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+struct PowerShell {}
+#[cfg(any(target_family = "windows", all(feature = "wsl", target_os = "linux")))]
+impl Provider for PowerShell {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        only_clipboard(sel).ok()?;
+        Some(powershell(match op {
+            Op::Copy => PS_COPY,
+            Op::Paste => PS_PASTE,
+        }))
+    }
+
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        if persistent() {
+            only_clipboard(sel)?;
+            return kept_copy(bytes);
+        }
+        self.command(sel, Op::Copy)
+            .ok_or(ClipError::Unsupported)?
+            .put(bytes)
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        if persistent() {
+            only_clipboard(sel)?;
+            return kept_paste();
+        }
+        self.command(sel, Op::Paste)
+            .ok_or(ClipError::Unsupported)?
+            .eat()
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        only_clipboard(sel)?;
+        if persistent() {
+            return kept("Set-Clipboard -Value $null", Op::Copy).map(drop);
+        }
+        powershell("Set-Clipboard -Value $null").run()
+    }
+}
+
+/// in an ssh session
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "haiku"))))]
+fn ssh() -> bool {
+    std::env::var_os("SSH_TTY").is_some() || std::env::var_os("SSH_CONNECTION").is_some()
+}
+
+/// is `c` somewhere on `PATH`
+fn has(c: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|p| std::env::split_paths(&p).any(|d| runnable(&d.join(c))))
+}
+
+#[cfg(unix)]
+fn runnable(p: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    p.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// windows leaves the extension off, see `PATHEXT`
+#[cfg(windows)]
+fn runnable(p: &Path) -> bool {
+    let exts = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_owned());
+    p.is_file()
+        || exts.split(';').filter(|e| !e.is_empty()).any(|e| {
+            let mut p = p.as_os_str().to_owned();
+            p.push(e);
+            Path::new(&p).is_file()
+        })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn runnable(p: &Path) -> bool {
+    p.is_file()
+}
+
+/// why we think this is WSL, if we do. `CLIPP_WSL=0` says it isn't, `CLIPP_WSL=1` that it is.
+#[cfg(all(feature = "wsl", target_os = "linux"))]
+fn wsl() -> Option<&'static str> {
+    match std::env::var("CLIPP_WSL").as_deref() {
+        Ok("0") => return None,
+        Ok("1") => return Some("CLIPP_WSL is 1"),
+        _ => {}
+    }
+    if let Some(why) = wsl_kernel(&std::fs::read_to_string("/proc/version").unwrap_or_default()) {
+        return Some(why);
+    }
+    // custom kernels don't say
+    if std::env::var_os("WSL_INTEROP").is_some() {
+        return Some("WSL_INTEROP is set");
+    }
+    std::env::var_os("WSL_DISTRO_NAME")
+        .is_some()
+        .then_some("WSL_DISTRO_NAME is set")
+}
+
+/// which WSL `/proc/version` is from
+#[cfg(all(feature = "wsl", target_os = "linux"))]
+fn wsl_kernel(version: &str) -> Option<&'static str> {
+    let v = version.to_lowercase();
+    if !v.contains("microsoft") {
+        return None;
+    }
+    // wsl2 runs a real kernel, "5.15.153.1-microsoft-standard-WSL2"; wsl1 makes one up, "4.4.0-19041-Microsoft"
+    Some(if v.contains("microsoft-standard") || v.contains("wsl2") {
+        "/proc/version says WSL2"
+    } else {
+        "/proc/version says WSL1"
+    })
+}
+
+/// something to copy with that outlives this process, for the backends that serve the selection from it.
+/// [`None`] if the `name`d backend outlives it already.
+pub fn handoff(name: &str) -> Result<Option<Arc<dyn Provider>>> {
+    if !matches!(name, "x11" | "wayland" | "portal") {
+        return Ok(None);
+    }
+    #[cfg(all(feature = "clippd", unix))]
+    if let Some(c) = clippd::Clippd::running() {
+        return Ok(Some(Arc::new(c)));
+    }
+    // these fork, and serve it from there
+    #[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() && has("wl-copy") {
+        return Ok(backend(Backend::WlCopy));
+    }
+    if std::env::var_os("DISPLAY").is_some() {
+        #[cfg(all(feature = "xclip", unix, not(target_os = "macos")))]
+        if has("xclip") {
+            return Ok(backend(Backend::XClip));
+        }
+        #[cfg(all(feature = "xsel", unix, not(target_os = "macos")))]
+        if has("xsel") {
+            return Ok(backend(Backend::XSel));
+        }
+    }
+    Err(ClipError::Unsupported)
+}
+
+/// a backend for `display`: an x11 one if it has a colon (`:1`), otherwise a wayland socket (`wayland-1`).
+/// the commands go first, as they outlive us, see [`handoff`].
+pub fn on_display(display: &str) -> Option<Chosen> {
+    let why = "Clipboard::on_display was called";
+    let chosen = |clip: Arc<dyn Provider>, b: Backend| Chosen {
+        clip,
+        name: b.name(),
+        why,
+        falls_back: false,
+    };
+    let display = Some(display.to_owned());
+    if display.as_deref().is_some_and(|d| d.contains(':')) {
+        #[cfg(all(feature = "xclip", unix, not(target_os = "macos")))]
+        if has("xclip") {
+            return Some(chosen(Arc::new(XClip { display }), Backend::XClip));
+        }
+        #[cfg(all(feature = "xsel", unix, not(target_os = "macos")))]
+        if has("xsel") {
+            return Some(chosen(Arc::new(XSel { display }), Backend::XSel));
+        }
+        #[cfg(all(feature = "x11", unix, not(target_os = "macos")))]
+        if let Some(x) = x11::X11::new(display.as_deref()) {
+            return Some(chosen(Arc::new(x), Backend::X11));
+        }
+    } else {
+        #[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+        if has("wl-copy") {
+            return Some(chosen(Arc::new(Wayland { display }), Backend::WlCopy));
+        }
+    }
+    None
+}
+
+/// klipper, over D-Bus, if it's running
+#[cfg(all(feature = "klipper-native", unix, not(target_os = "macos")))]
+fn klipper() -> Result<klipper::Klipper> {
+    klipper::Klipper::new().ok_or(ClipError::NoBackend)
+}
+
+/// klipper, through `qdbus`, if both are installed
+#[cfg(all(
+    feature = "klipper",
+    not(feature = "klipper-native"),
+    unix,
+    not(target_os = "macos")
+))]
+fn klipper() -> Result<Klipper> {
+    if has("klipper") && has("qdbus") {
+        Ok(Klipper {})
+    } else {
+        Err(ClipError::NoBackend)
+    }
+}
+
+/// the copyq clipboard manager, so what's copied goes through it.
+/// `copy` and `selection` take what's on stdin for a `-`
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+struct CopyQ {}
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+impl CopyQ {
+    /// the client fails when the server isn't
+    fn running() -> bool {
+        has("copyq") && c!("copyq" "count").stdout(Stdio::null()).run().is_ok()
+    }
+
+    fn count() -> Result<usize> {
+        let n = c!("copyq" "count").eat()?;
+        Ok(text(&n)?.trim().parse().unwrap_or(0))
+    }
+
+    fn history() -> Result<Vec<String>> {
+        (0..Self::count()?)
+            .map(|n| {
+                let s = Self::read("text/plain", n)?;
+                String::from_utf8(s).map_err(ClipError::Utf8)
+            })
+            .collect()
+    }
+
+    fn history_item(n: usize) -> Result<Option<String>> {
+        if n >= Self::count()? {
+            return Ok(None);
+        }
+        let s = Self::read("text/plain", n)?;
+        String::from_utf8(s).map(Some).map_err(ClipError::Utf8)
+    }
+
+    fn read(mime: &str, n: usize) -> Result<Vec<u8>> {
+        c!("copyq" "read").arg(mime).arg(n.to_string()).eat()
+    }
+
+    /// a new item at the top of the history, which doesn't touch the clipboard
+    fn add(mime: &str, bytes: &[u8]) -> Result<()> {
+        c!("copyq" "write" "0").arg(mime).arg("-").put(bytes)
+    }
+
+    fn get(sel: Selection) -> Command {
+        let mut c = c!("copyq");
+        c.arg(match sel {
+            Selection::Clipboard => "clipboard",
+            Selection::Primary => "selection",
+        });
+        c
+    }
+
+    /// copying, with the formats, each a mime type then its data (or `-` for stdin), still to add
+    fn copying(sel: Selection) -> Command {
+        let mut c = c!("copyq");
+        c.arg(match sel {
+            Selection::Clipboard => "copy",
+            Selection::Primary => "copySelection",
+        });
+        c
+    }
+
+    fn set(sel: Selection, mime: &str) -> Command {
+        let mut c = Self::copying(sel);
+        c.args([mime, "-"]);
+        c
+    }
+}
+
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+impl Provider for CopyQ {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        Some(match op {
+            Op::Copy => Self::set(sel, "text/plain"),
+            Op::Paste => Self::get(sel),
+        })
+    }
+
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        eat_into(self, sel, buf)
+    }
+
+    fn paste_to_writer(&self, sel: Selection, w: &mut dyn Write) -> Result<()> {
+        eat_to(self, sel, w)
+    }
+
+    fn copy_from_reader(&self, sel: Selection, r: &mut dyn Read) -> Result<()> {
+        put_from(self, sel, r)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.copy_bytes(sel, &[])
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        Self::set(sel, mime).put(bytes)
+    }
+
+    fn copy_formats(&self, sel: Selection, formats: &[(&str, &[u8])]) -> Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+        // only one can come on stdin, so the text does, out of sight of ps; the rest are arguments
+        let piped = formats.iter().position(|(m, _)| plain(m)).unwrap_or(0);
+        let Some(&(_, bytes)) = formats.get(piped) else {
+            return Err(ClipError::Unsupported);
+        };
+        let mut c = Self::copying(sel);
+        for (i, &(mime, data)) in formats.iter().enumerate() {
+            c.arg(mime);
+            if i == piped {
+                c.arg("-");
+            } else {
+                c.arg(std::ffi::OsStr::from_bytes(data));
+            }
+        }
+        c.put(bytes)
+    }
+
+    fn formats(&self, sel: Selection) -> Result<Vec<String>> {
+        let t = Self::get(sel).arg("?").eat()?;
+        Ok(lines(&t).map(str::to_owned).collect())
+    }
+
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        if !self.formats(sel)?.iter().any(|t| t == mime) {
+            return Ok(None);
+        }
+        Self::get(sel).arg(mime).eat().map(Some)
+    }
+}
+
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+fn copyq() -> Result<()> {
+    if has("copyq") {
+        Ok(())
+    } else {
+        Err(ClipError::NoBackend)
+    }
+}
+
+/// copyq's first tab, newest first
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+pub fn copyq_history() -> Result<Vec<String>> {
+    copyq()?;
+    CopyQ::history()
+}
+
+/// the `n`th item in copyq's first tab
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+pub fn copyq_history_item(n: usize) -> Result<Option<String>> {
+    copyq()?;
+    CopyQ::history_item(n)
+}
+
+/// a new item atop copyq's first tab
+#[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+pub fn copyq_add(mime: &str, bytes: &[u8]) -> Result<()> {
+    copyq()?;
+    CopyQ::add(mime, bytes)
+}
+
+/// everything klipper remembers, newest (what's on the clipboard) first
+#[cfg(all(feature = "klipper", unix, not(target_os = "macos")))]
+pub fn klipper_history() -> Result<Vec<String>> {
+    klipper()?.history()
+}
+
+/// the `n`th thing klipper remembers, 0 being what's on the clipboard
+#[cfg(all(feature = "klipper", unix, not(target_os = "macos")))]
+pub fn klipper_history_item(n: usize) -> Result<Option<String>> {
+    klipper()?.history_item(n)
+}
+
+/// a named macos pasteboard, through `macos-native`
+#[allow(clippy::unnecessary_wraps)]
+#[cfg_attr(
+    not(all(target_os = "macos", feature = "macos-native")),
+    allow(unused_variables)
+)]
+pub fn named(name: &str) -> Option<Chosen> {
+    #[cfg(all(target_os = "macos", feature = "macos-native"))]
+    return Some(Chosen {
+        clip: Arc::new(macos::Pasteboard {
+            name: Some(name.to_owned()),
+        }),
+        name: Backend::Pasteboard.name(),
+        why: "Clipboard::named was called",
+        falls_back: false,
+    });
+    #[cfg(not(all(target_os = "macos", feature = "macos-native")))]
+    None
+}
+
+/// the provider for `b`, if it's built
+pub fn backend(b: Backend) -> Option<Arc<dyn Provider>> {
+    match b {
+        #[cfg(all(target_family = "windows", feature = "windows-native"))]
+        Backend::Windows => Some(Arc::new(Windows {})),
+        #[cfg(target_family = "windows")]
+        Backend::PowerShell => Some(Arc::new(PowerShell {})),
+        #[cfg(all(target_os = "macos", feature = "macos-native"))]
+        Backend::Pasteboard => Some(Arc::new(macos::Pasteboard::default())),
+        #[cfg(target_os = "macos")]
+        Backend::PbCopy => Some(Arc::new(PbCopy {})),
+        #[cfg(target_os = "haiku")]
+        Backend::Haiku => Some(Arc::new(Haiku {})),
+        #[cfg(all(feature = "wsl", target_os = "linux"))]
+        Backend::Wsl => Some(Arc::new(PowerShell {})),
+        #[cfg(all(feature = "portal", unix, not(target_os = "macos")))]
+        Backend::Portal => portal::Portal::new().map(|p| Arc::new(p) as _),
+        #[cfg(all(feature = "wayland-native", unix, not(target_os = "macos")))]
+        Backend::Wayland => wayland::WlClipboard::new().map(|p| Arc::new(p) as _),
+        #[cfg(all(feature = "wayland", unix, not(target_os = "macos")))]
+        Backend::WlCopy => Some(Arc::new(Wayland::default())),
+        #[cfg(all(feature = "x11", unix, not(target_os = "macos")))]
+        Backend::X11 => x11::X11::new(None).map(|p| Arc::new(p) as _),
+        #[cfg(all(feature = "xsel", unix, not(target_os = "macos")))]
+        Backend::XSel => Some(Arc::new(XSel::default())),
+        #[cfg(all(feature = "xclip", unix, not(target_os = "macos")))]
+        Backend::XClip => Some(Arc::new(XClip::default())),
+        #[cfg(all(feature = "klipper-native", unix, not(target_os = "macos")))]
+        Backend::Klipper => klipper::Klipper::new().map(|p| Arc::new(p) as _),
+        #[cfg(all(
+            feature = "klipper",
+            not(feature = "klipper-native"),
+            unix,
+            not(target_os = "macos")
+        ))]
+        Backend::Klipper => Some(Arc::new(Klipper {})),
+        #[cfg(all(feature = "copyq", unix, not(target_os = "macos")))]
+        Backend::CopyQ => Some(Arc::new(CopyQ {})),
+        #[cfg(all(feature = "tmux", unix, not(target_os = "macos")))]
+        Backend::Tmux => Some(Arc::new(Tmux {})),
+        #[cfg(all(feature = "screen", unix, not(target_os = "macos")))]
+        Backend::Screen => Some(Arc::new(Screen {})),
+        #[cfg(all(feature = "clippd", unix))]
+        Backend::Clippd => clippd::Clippd::running().map(|p| Arc::new(p) as _),
+        #[cfg(all(feature = "lemonade", not(target_family = "wasm")))]
+        Backend::Lemonade => Some(Arc::new(Lemonade {})),
+        #[cfg(all(feature = "clipper", not(target_family = "wasm")))]
+        Backend::Clipper => Some(Arc::new(Clipper {})),
+        #[cfg(all(feature = "osc52", unix, not(target_os = "macos")))]
+        Backend::Osc52 => Some(Arc::new(osc52::Osc52 {})),
+        #[cfg(all(feature = "web", target_family = "wasm"))]
+        Backend::Web => web::Web::new().map(|p| Arc::new(p) as _),
+        Backend::Memory => Some(Arc::new(Memory::default())),
+        _ => None,
+    }
+}
+
+/// a provider, and why it was picked
+#[derive(Clone)]
+pub struct Chosen {
+    pub clip: Arc<dyn Provider>,
+    pub name: &'static str,
+    pub why: &'static str,
+    /// detected, so when it breaks the next one can be detected instead
+    pub falls_back: bool,
+}
+
+/// the programs `b` runs, so it's only chosen with them installed
+fn tools(b: Backend) -> &'static [&'static str] {
+    match b {
+        Backend::PowerShell | Backend::Wsl => &["powershell.exe"],
+        Backend::PbCopy => &["pbcopy", "pbpaste"],
+        Backend::Haiku => &["clipboard"],
+        Backend::WlCopy => &["wl-copy", "wl-paste"],
+        Backend::XSel => &["xsel"],
+        Backend::XClip => &["xclip"],
+        Backend::Klipper if !cfg!(feature = "klipper-native") => &["qdbus"],
+        Backend::CopyQ => &["copyq"],
+        Backend::Tmux => &["tmux"],
+        Backend::Screen => &["screen"],
+        Backend::Lemonade => &["lemonade"],
+        _ => &[],
+    }
+}
+
+impl Chosen {
+    pub fn new(b: Backend, why: &'static str) -> Option<Self> {
+        if !tools(b).iter().all(|t| has(t)) {
+            return None;
+        }
+        backend(b).map(|clip| Self {
+            clip,
+            name: b.name(),
+            why,
+            falls_back: false,
+        })
+    }
+
+    /// `b`, unless it's to be skipped, i.e. it already failed
+    fn found(b: Backend, why: &'static str, skip: Skip) -> Option<Self> {
+        if skip(b) {
+            return None;
+        }
+        Self::new(b, why).map(|c| Self {
+            falls_back: true,
+            ..c
+        })
+    }
+}
+
+pub fn provide() -> Option<Chosen> {
+    #[cfg(all(feature = "config", not(target_family = "wasm")))]
+    let config = crate::config::apply();
+    if let Ok(b) = std::env::var("CLIPP_BACKEND") {
+        if let Ok(b) = b.parse() {
+            return Chosen::new(b, "CLIPP_BACKEND is set");
+        }
+        event!(
+            warn,
+            "CLIPP_BACKEND={b:?} isn't a backend, so looking for one"
+        );
+    }
+    #[cfg(all(feature = "config", not(target_family = "wasm")))]
+    if let Some(c) = config.chosen() {
+        return Some(c);
+    }
+    if let Some(c) = detect(&[]) {
+        return Some(c);
+    }
+    if let Ok(b) = std::env::var("CLIPP_FALLBACK") {
+        if let Ok(b) = b.parse() {
+            return Chosen::new(b, "nothing was found and CLIPP_FALLBACK is set");
+        }
+        event!(
+            warn,
+            "CLIPP_FALLBACK={b:?} isn't a backend, so it's ignored"
+        );
+    }
+    #[cfg(all(feature = "config", not(target_family = "wasm")))]
+    return config.fallback();
+    #[cfg(not(all(feature = "config", not(target_family = "wasm"))))]
+    None
+}
+
+/// backends that [`detect`] doesn't want
+type Skip<'a> = &'a dyn Fn(Backend) -> bool;
+
+/// which backends to look for first, in order. empty asks `CLIPP_PRIORITY`
+static PRIORITY: Mutex<Vec<Backend>> = Mutex::new(vec![]);
+
+pub fn set_priority(order: &[Backend]) {
+    *PRIORITY.lock().unwrap_or_else(PoisonError::into_inner) = order.to_vec();
+}
+
+fn priority() -> Vec<Backend> {
+    let set = PRIORITY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if !set.is_empty() {
+        return set;
+    }
+    std::env::var("CLIPP_PRIORITY")
+        .map(|p| p.split(',').filter_map(|b| b.parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// the best backend here, skipping the ones that `failed`.
+/// the prioritized ones are each looked for on their own first, as they would be anyway
+pub fn detect(failed: &[&str]) -> Option<Chosen> {
+    let failed = |b: Backend| failed.contains(&b.name());
+    priority()
+        .into_iter()
+        .find_map(|p| search(&|b| b != p || failed(b)))
+        .or_else(|| search(&failed))
+}
+
+fn search(skip: Skip) -> Option<Chosen> {
+    #[cfg(target_family = "windows")]
+    return Chosen::found(Backend::Windows, "on windows", skip).or_else(|| {
+        Chosen::found(
+            Backend::PowerShell,
+            "on windows, without windows-native (or it failed)",
+            skip,
+        )
+    });
+    #[cfg(target_os = "macos")]
+    return Chosen::found(Backend::Pasteboard, "on macos", skip).or_else(|| {
+        Chosen::found(
+            Backend::PbCopy,
+            "on macos, without macos-native (or it failed)",
+            skip,
+        )
+    });
+    #[cfg(target_os = "haiku")]
+    return Chosen::found(Backend::Haiku, "on haiku", skip);
+    #[cfg(all(unix, not(any(target_os = "macos", target_os = "haiku"))))]
+    return unix(skip);
+    #[cfg(all(feature = "web", target_family = "wasm"))]
+    return Chosen::found(Backend::Web, "in a browser", skip);
+    #[cfg(not(any(
+        target_family = "windows",
+        unix,
+        all(feature = "web", target_family = "wasm")
+    )))]
+    return None;
+}
+
+/// x11, wayland, and friends
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "haiku"))))]
+fn unix(skip: Skip) -> Option<Chosen> {
+    let found = |b, why| Chosen::found(b, why, skip);
+    // it checks that it's running, and it does the rest with whatever it found
+    #[cfg(feature = "clippd")]
+    if let Some(c) = found(Backend::Clippd, "clippd is running") {
+        return Some(c);
+    }
+    #[cfg(all(feature = "wsl", target_os = "linux"))]
+    if let Some(why) = wsl() {
+        if let Some(c) = found(Backend::Wsl, why) {
+            return Some(c);
+        }
+    }
+    // the usual tools are missing or fenced off in there
+    #[cfg(feature = "portal")]
+    if portal::sandboxed() {
+        if let Some(c) = found(Backend::Portal, "in a flatpak or snap sandbox") {
+            return Some(c);
+        }
+    }
+    // a clipboard manager the user runs should see what's copied, and it does the copying
+    #[cfg(feature = "copyq")]
+    if CopyQ::running() {
+        if let Some(c) = found(Backend::CopyQ, "copyq is running") {
+            return Some(c);
+        }
+    }
+    if std::env::var("WAYLAND_DISPLAY").is_ok() {
+        #[cfg(feature = "wayland-native")]
+        if let Some(c) = found(Backend::Wayland, "WAYLAND_DISPLAY is set") {
+            return Some(c);
+        }
+        #[cfg(feature = "wayland")]
+        if has("wl-copy") {
+            if let Some(c) = found(
+                Backend::WlCopy,
+                "WAYLAND_DISPLAY is set and wl-copy is installed",
+            ) {
+                return Some(c);
+            }
+        }
+    }
+    if std::env::var("DISPLAY").is_ok() {
+        #[cfg(feature = "x11")]
+        if let Some(c) = found(Backend::X11, "DISPLAY is set") {
+            return Some(c);
+        }
+        #[cfg(feature = "xsel")]
+        if has("xsel") {
+            if let Some(c) = found(Backend::XSel, "DISPLAY is set and xsel is installed") {
+                return Some(c);
+            }
+        }
+        #[cfg(feature = "xclip")]
+        if has("xclip") {
+            if let Some(c) = found(Backend::XClip, "DISPLAY is set and xclip is installed") {
+                return Some(c);
+            }
+        }
+        // it checks that klipper is running
+        #[cfg(feature = "klipper-native")]
+        if let Some(c) = found(Backend::Klipper, "DISPLAY is set and klipper is on the bus") {
+            return Some(c);
+        }
+        #[cfg(all(feature = "klipper", not(feature = "klipper-native")))]
+        if has("klipper") && has("qdbus") {
+            if let Some(c) = found(
+                Backend::Klipper,
+                "DISPLAY is set and klipper and qdbus are installed",
+            ) {
+                return Some(c);
+            }
+        }
+    }
+    // no display here, so the clipboard that matters is on the other end.
+    // a tmux or screen buffer on this one would keep it from ever getting there
+    if ssh() {
+        if let Some(c) = remote(skip) {
+            return Some(c);
+        }
+    }
+    #[cfg(feature = "tmux")]
+    if std::env::var("TMUX").is_ok() && has("tmux") {
+        if let Some(c) = found(Backend::Tmux, "TMUX is set and tmux is installed") {
+            return Some(c);
+        }
+    }
+    #[cfg(feature = "screen")]
+    if std::env::var("STY").is_ok() && has("screen") {
+        if let Some(c) = found(Backend::Screen, "STY is set and screen is installed") {
+            return Some(c);
+        }
+    }
+    #[cfg(feature = "osc52")]
+    if osc52::Osc52::available() {
+        return found(Backend::Osc52, "there is a terminal, but no display");
+    }
+    None
+}
+
+/// what reaches the clipboard on the other end of ssh
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "haiku"))))]
+fn remote(skip: Skip) -> Option<Chosen> {
+    let found = |b, why| Chosen::found(b, why, skip);
+    // the other end of a tunnel to the machine with the clipboard
+    #[cfg(feature = "lemonade")]
+    if has("lemonade") {
+        if let Some(c) = found(Backend::Lemonade, "over ssh, and lemonade is installed") {
+            return Some(c);
+        }
+    }
+    #[cfg(feature = "clipper")]
+    if Clipper::socket().is_some() {
+        if let Some(c) = found(Backend::Clipper, "over ssh, and clipper's socket is there") {
+            return Some(c);
+        }
+    }
+    #[cfg(feature = "osc52")]
+    if osc52::Osc52::over_ssh() {
+        if let Some(c) = found(Backend::Osc52, "over ssh, with no display") {
+            return Some(c);
+        }
+    }
+    None
+}
+
+#[test]
+fn test() {
+    #[allow(unused_macros)]
+    macro_rules! test {
+        ($clipboard:expr) => {
+            $clipboard.copy(Selection::Clipboard, "text").unwrap();
+            assert_eq!($clipboard.paste(Selection::Clipboard).unwrap(), "text");
+            $clipboard.clear(Selection::Clipboard).unwrap();
+        };
+    }
+    #[cfg(target_os = "macos")]
+    test!(PbCopy {});
+    #[cfg(target_os = "haiku")]
+    test!(Haiku {});
+    #[cfg(all(feature = "xclip", target_os = "linux"))]
+    test!(XClip::default());
+    #[cfg(all(feature = "xsel", target_os = "linux"))]
+    test!(XSel::default());
+    #[cfg(all(feature = "wayland", target_os = "linux"))]
+    if std::env::var("WAYLAND_DISPLAY").is_ok() {
+        test!(Wayland::default());
+    }
+    #[cfg(all(
+        feature = "klipper",
+        not(feature = "klipper-native"),
+        target_os = "linux"
+    ))]
+    test!(Klipper {});
+    #[cfg(all(feature = "klipper-native", target_os = "linux"))]
+    if let Some(k) = klipper::Klipper::new() {
+        test!(k);
+    }
+    #[cfg(all(feature = "copyq", target_os = "linux"))]
+    if CopyQ::running() {
+        test!(CopyQ {});
+    }
+    #[cfg(all(feature = "tmux", target_os = "linux"))]
+    if std::env::var("TMUX").is_ok() {
+        test!(Tmux {});
+    }
+    #[cfg(all(feature = "screen", target_os = "linux"))]
+    if std::env::var("STY").is_ok() {
+        test!(Screen {});
+    }
+    #[cfg(all(target_family = "windows", feature = "windows-native"))]
+    test!(Windows {});
+    #[cfg(target_family = "windows")]
+    test!(PowerShell {});
+    #[cfg(all(feature = "wsl", target_os = "linux"))]
+    if wsl().is_some() {
+        test!(PowerShell {});
+    }
+}
+
+#[cfg(all(feature = "wsl", target_os = "linux"))]
+#[test]
+fn wsl_version() {
+    assert_eq!(
+        wsl_kernel("Linux version 5.15.153.1-microsoft-standard-WSL2 (root@941d701f84f1)"),
+        Some("/proc/version says WSL2")
+    );
+    assert_eq!(
+        wsl_kernel("Linux version 4.4.0-19041-Microsoft (Microsoft@Microsoft.com)"),
+        Some("/proc/version says WSL1")
+    );
+    assert_eq!(wsl_kernel("Linux version 6.8.0-45-generic"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn timeout_kills() {
+    let start = Instant::now();
+    let ch = c!("sleep" "10").spawn().unwrap();
+    assert!(matches!(
+        wait_for(ch, Some(Duration::from_millis(50))),
+        Err(ClipError::Timeout)
+    ));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    let ch = c!("true").spawn().unwrap();
+    assert!(wait_for(ch, Some(Duration::from_secs(5)))
+        .unwrap()
+        .success());
+}
+
+#[cfg(unix)]
+#[test]
+fn stderr() {
+    let Err(ClipError::Status { stderr, .. }) = c!("sh" "-c" "echo oh no >&2; exit 3").run() else {
+        panic!()
+    };
+    assert_eq!(stderr, "oh no");
+}
+
+#[cfg(unix)]
+#[test]
+fn path_search() {
+    assert!(has("sh"));
+    assert!(!has("clipp-surely-not-a-command"));
+    // not executable
+    assert!(!has("../etc/passwd"));
+}
+
+#[test]
+fn text_targets() {
+    let has = |f: &[&str]| f.iter().map(|&m| m.to_owned()).collect::<Vec<_>>();
+    assert_eq!(
+        best_text(&has(&["TIMESTAMP", "STRING", "UTF8_STRING"])),
+        Some("UTF8_STRING")
+    );
+    assert_eq!(best_text(&has(&["text/html", "STRING"])), Some("STRING"));
+    assert_eq!(best_text(&has(&["text/html"])), None);
+    assert_eq!(best_text(&has(&["image/png"])), None);
+    assert_eq!(from_latin1(b"caf\xe9"), "café".as_bytes());
+}
+
+#[test]
+fn preference() {
+    let prefs = ["text/markdown", "text/html", "text/plain", "text/rtf"].map(str::to_owned);
+    let has = |f: &[&str]| f.iter().map(|&m| m.to_owned()).collect::<Vec<_>>();
+    assert_eq!(
+        pick(&prefs, &has(&["text/plain", "text/html"])),
+        Some("text/html")
+    );
+    assert_eq!(
+        pick(&prefs, &has(&["text/html", "text/markdown"])),
+        Some("text/markdown")
+    );
+    // the text, before it gets to rtf
+    assert_eq!(pick(&prefs, &has(&["UTF8_STRING", "text/rtf"])), None);
+    assert_eq!(pick(&[], &has(&["text/html"])), None);
+}
+
+#[test]
+fn html_format() {
+    let h = cf_html("<b>hi</b>");
+    assert_eq!(
+        from_cf_html(h.as_bytes()),
+        Some(
+            &b"<html><body>\r\n<!--StartFragment--><b>hi</b><!--EndFragment-->\r\n</body></html>"[..]
+        )
+    );
+}
+
+#[test]
+fn dib() {
+    // a 1x1 24 bit bmp
+    let mut bmp = b"BM".to_vec();
+    for n in [58u32, 0, 54, 40, 1, 1] {
+        bmp.extend_from_slice(&n.to_le_bytes());
+    }
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&24u16.to_le_bytes());
+    for n in [0u32, 4, 0, 0, 0, 0] {
+        bmp.extend_from_slice(&n.to_le_bytes());
+    }
+    bmp.extend_from_slice(&[0xff, 0, 0, 0]);
+    let dib = to_dib(&bmp).unwrap();
+    assert_eq!(dib.len(), 44);
+    assert_eq!(from_dib(dib).unwrap(), bmp);
+    assert_eq!(to_dib(b"not a bmp"), None);
+    // as many colors as can be said
+    let mut huge = dib.to_vec();
+    huge[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(from_dib(&huge), None);
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn dibv5() {
+    let mut img = image::RgbaImage::new(3, 2);
+    img.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+    img.put_pixel(2, 1, image::Rgba([0, 0, 255, 128]));
+    let png = png_from_dib(&to_dibv5(&img)).unwrap();
+    let back = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap();
+    assert_eq!(back.to_rgba8(), img);
+}
+
+#[test]
+fn items_skip() {
+    struct Picky;
+    impl Provider for Picky {
+        fn copy_bytes(&self, _: Selection, _: &[u8]) -> Result<()> {
+            Ok(())
+        }
+        fn paste_bytes(&self, _: Selection) -> Result<Vec<u8>> {
+            Ok(b"hi".to_vec())
+        }
+        fn clear(&self, _: Selection) -> Result<()> {
+            Ok(())
+        }
+        fn formats(&self, _: Selection) -> Result<Vec<String>> {
+            Ok(["TIMESTAMP", "text/plain", "image/png"]
+                .map(str::to_owned)
+                .into())
+        }
+        fn paste_mime(&self, _: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+            match mime {
+                "text/plain" => Ok(Some(b"hi".to_vec())),
+                _ => Err(ClipError::Unsupported),
+            }
+        }
     }
+    assert_eq!(
+        Picky.paste_items(Selection::Clipboard).unwrap(),
+        [vec![("text/plain".to_owned(), b"hi".to_vec())]]
+    );
 }
@@ -0,0 +1,52 @@
+//! base64, for the clipboards that only take text
+const A: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for c in bytes.chunks(3) {
+        let n = c
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= c.len() {
+                s.push(A[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+/// [`None`] if it isn't base64
+pub fn decode(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim_end_matches('=').as_bytes();
+    let mut v = Vec::with_capacity(s.len() * 3 / 4);
+    for c in s.chunks(4) {
+        if c.len() == 1 {
+            return None;
+        }
+        let n = c.iter().enumerate().try_fold(0u32, |n, (i, b)| {
+            let (_, d) = A.iter().zip(0u32..).find(|(a, _)| *a == b)?;
+            Some(n | d << (18 - 6 * i))
+        })?;
+        v.extend(&n.to_be_bytes()[1..c.len()]);
+    }
+    Some(v)
+}
+
+#[test]
+fn base64() {
+    for (d, e) in [
+        (&b""[..], ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        ("wörld!".as_bytes(), "d8O2cmxkIQ=="),
+    ] {
+        assert_eq!(encode(d), e);
+        assert_eq!(decode(e).unwrap(), d);
+    }
+    assert_eq!(decode("Zm9v!"), None);
+}
@@ -0,0 +1,214 @@
+//! the clippd daemon, over its socket. what it's asked, it does with the clipboard it found itself
+use super::{fits, Provider};
+use crate::{ClipError, Result, Selection};
+use std::{
+    io::{ErrorKind, Read, Write},
+    os::unix::{
+        fs::{FileTypeExt, MetadataExt},
+        net::UnixStream,
+    },
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// set in the daemon, so it never finds itself
+pub static SERVING: AtomicBool = AtomicBool::new(false);
+
+/// where clippd listens: `$XDG_RUNTIME_DIR/clippd.sock`, or the temporary directory without one
+pub fn socket() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR").map_or_else(
+        || {
+            let user = std::env::var("USER").unwrap_or_default();
+            std::env::temp_dir().join(format!("clippd-{user}.sock"))
+        },
+        |d| PathBuf::from(d).join("clippd.sock"),
+    )
+}
+
+/// if `path` is a socket of this user's. without `XDG_RUNTIME_DIR` it's somewhere anyone can write,
+/// and anyone else's would hear everything that's copied
+pub fn ours(path: &Path) -> bool {
+    std::fs::symlink_metadata(path)
+        .is_ok_and(|m| m.file_type().is_socket() && m.uid() == rustix::process::getuid().as_raw())
+}
+
+/// what's asked of it, each on a connection of its own:
+/// an op, the selection, and then a mime type and the data, framed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Ask {
+    Copy = b'c',
+    /// the data is mime types and what's in them, one frame each, taking turns
+    CopyFormats = b'm',
+    Paste = b'p',
+    Clear = b'x',
+    Formats = b'f',
+    History = b'h',
+}
+
+impl Ask {
+    pub fn from_byte(b: u8) -> Option<Self> {
+        [
+            Self::Copy,
+            Self::CopyFormats,
+            Self::Paste,
+            Self::Clear,
+            Self::Formats,
+            Self::History,
+        ]
+        .into_iter()
+        .find(|a| *a as u8 == b)
+    }
+}
+
+/// how it answers, before the data
+pub const OK: u8 = 0;
+/// [`ClipError::Unsupported`]
+pub const UNSUPPORTED: u8 = 1;
+/// nothing of that mime type
+pub const ABSENT: u8 = 2;
+/// anything else, with what went wrong
+pub const FAILED: u8 = 3;
+
+/// the length as 4 big endian bytes, then `b`
+pub fn write_frame(w: &mut impl Write, b: &[u8]) -> std::io::Result<()> {
+    let len = u32::try_from(b.len()).map_err(|_| ErrorKind::InvalidInput)?;
+    w.write_all(&len.to_be_bytes())?;
+    w.write_all(b)
+}
+
+pub fn read_frame(r: &mut impl Read) -> Result<Vec<u8>> {
+    let mut len = [0; 4];
+    r.read_exact(&mut len).map_err(ClipError::Io)?;
+    let len = u32::from_be_bytes(len);
+    fits(usize::try_from(len).unwrap_or(usize::MAX))?;
+    let mut b = vec![];
+    r.take(len.into())
+        .read_to_end(&mut b)
+        .map_err(ClipError::Io)?;
+    if b.len() < len as usize {
+        return Err(ClipError::Io(ErrorKind::UnexpectedEof.into()));
+    }
+    Ok(b)
+}
+
+/// frames, one after another
+pub fn join<T: AsRef<[u8]>>(all: &[T]) -> Vec<u8> {
+    let mut b = vec![];
+    for s in all {
+        _ = write_frame(&mut b, s.as_ref());
+    }
+    b
+}
+
+pub fn split_bytes(mut b: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut all = vec![];
+    while !b.is_empty() {
+        all.push(read_frame(&mut b)?);
+    }
+    Ok(all)
+}
+
+pub fn split(b: &[u8]) -> Result<Vec<String>> {
+    split_bytes(b)?
+        .into_iter()
+        .map(|s| String::from_utf8(s).map_err(ClipError::Utf8))
+        .collect()
+}
+
+pub struct Clippd {
+    path: PathBuf,
+}
+
+impl Clippd {
+    /// if it's listening, and this isn't it
+    pub fn running() -> Option<Self> {
+        if SERVING.load(Ordering::Relaxed) {
+            return None;
+        }
+        let path = socket();
+        if !ours(&path) {
+            return None;
+        }
+        UnixStream::connect(&path).ok()?;
+        Some(Self { path })
+    }
+
+    /// what it answered, [`None`] being [`ABSENT`]
+    pub fn ask(
+        &self,
+        ask: Ask,
+        sel: Selection,
+        mime: &str,
+        data: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        let mut s = UnixStream::connect(&self.path).map_err(ClipError::Io)?;
+        let sel = match sel {
+            Selection::Clipboard => b'c',
+            Selection::Primary => b'p',
+        };
+        s.write_all(&[ask as u8, sel])
+            .and_then(|()| write_frame(&mut s, mime.as_bytes()))
+            .and_then(|()| write_frame(&mut s, data))
+            .map_err(ClipError::Io)?;
+        let mut status = [0];
+        s.read_exact(&mut status).map_err(ClipError::Io)?;
+        let answer = read_frame(&mut s)?;
+        match status[0] {
+            OK => Ok(Some(answer)),
+            UNSUPPORTED => Err(ClipError::Unsupported),
+            ABSENT => Ok(None),
+            _ => Err(ClipError::Backend(
+                String::from_utf8_lossy(&answer).into_owned().into(),
+            )),
+        }
+    }
+
+    fn some(&self, ask: Ask, sel: Selection, mime: &str, data: &[u8]) -> Result<Vec<u8>> {
+        self.ask(ask, sel, mime, data)?
+            .ok_or(ClipError::Unsupported)
+    }
+}
+
+impl Provider for Clippd {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        self.some(Ask::Copy, sel, "", bytes).map(drop)
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        self.some(Ask::Paste, sel, "", &[])
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.some(Ask::Clear, sel, "", &[]).map(drop)
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        self.some(Ask::Copy, sel, mime, bytes).map(drop)
+    }
+
+    fn copy_formats(&self, sel: Selection, formats: &[(&str, &[u8])]) -> Result<()> {
+        let all: Vec<&[u8]> = formats
+            .iter()
+            .flat_map(|&(m, b)| [m.as_bytes(), b])
+            .collect();
+        self.some(Ask::CopyFormats, sel, "", &join(&all)).map(drop)
+    }
+
+    fn formats(&self, sel: Selection) -> Result<Vec<String>> {
+        split(&self.some(Ask::Formats, sel, "", &[])?)
+    }
+
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        self.ask(Ask::Paste, sel, mime, &[])
+    }
+}
+
+#[test]
+fn frames() {
+    let all = ["one".to_owned(), String::new(), "two\nlines".into()];
+    assert_eq!(split(&join(&all)).unwrap(), all);
+    assert!(split(&join(&all)[..5]).is_err());
+    assert_eq!(Ask::from_byte(b'h'), Some(Ask::History));
+    assert_eq!(Ask::from_byte(b'?'), None);
+}
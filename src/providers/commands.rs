@@ -0,0 +1,125 @@
+//! a clipboard that's whatever commands the user gives
+use super::{eat_into, eat_to, put_from, Op, Provider, Put};
+use crate::{ClipError, Result, Selection};
+use std::{
+    io::{Read, Write},
+    process::Command,
+};
+
+/// A clipboard made of two commands: one that takes what's copied on stdin, and one that prints what's pasted.
+/// For remotes, serial consoles, and tools clipp doesn't know. Install it with [`set_provider`](crate::set_provider).
+///
+/// The commands are split into words on spaces, keeping what's in quotes together, and run without a shell.
+/// `{selection}` in either becomes `clipboard` or `primary`; without it, there's only the clipboard.
+///
+/// ```no_run
+/// use clipp::Commands;
+/// clipp::set_provider(Commands::new("my-copy --stdin", "my-paste")).unwrap();
+/// // another machine's clipboard, and its primary selection
+/// let remote = Commands::new(
+///     "ssh box 'xclip -sel {selection}'",
+///     "ssh box 'xclip -o -sel {selection}'",
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Commands {
+    copy: Vec<String>,
+    paste: Vec<String>,
+}
+
+impl Commands {
+    /// Copy with `copy`, and paste with `paste`.
+    #[must_use]
+    pub fn new(copy: &str, paste: &str) -> Self {
+        Self {
+            copy: words(copy),
+            paste: words(paste),
+        }
+    }
+
+    fn make(&self, sel: Selection, op: Op) -> Option<Command> {
+        let words = match op {
+            Op::Copy => &self.copy,
+            Op::Paste => &self.paste,
+        };
+        let sel = match sel {
+            Selection::Clipboard => "clipboard",
+            Selection::Primary if words.iter().any(|w| w.contains("{selection}")) => "primary",
+            Selection::Primary => return None,
+        };
+        let (program, args) = words.split_first()?;
+        let mut c = Command::new(program);
+        c.args(args.iter().map(|a| a.replace("{selection}", sel)));
+        Some(c)
+    }
+}
+
+impl Provider for Commands {
+    fn command(&self, sel: Selection, op: Op) -> Option<Command> {
+        self.make(sel, op)
+    }
+
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        eat_into(self, sel, buf)
+    }
+
+    fn paste_to_writer(&self, sel: Selection, w: &mut dyn Write) -> Result<()> {
+        eat_to(self, sel, w)
+    }
+
+    fn copy_from_reader(&self, sel: Selection, r: &mut dyn Read) -> Result<()> {
+        put_from(self, sel, r)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.make(sel, Op::Copy)
+            .ok_or(ClipError::Unsupported)?
+            .put([])
+    }
+}
+
+/// split on whitespace, except in `'` or `"` quotes
+fn words(s: &str) -> Vec<String> {
+    let mut all = vec![];
+    let mut word = None::<String>;
+    let mut quote = None;
+    for c in s.chars() {
+        match (quote, c) {
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (Some(q), _) if q == c => quote = None,
+            (None, _) if c.is_whitespace() => all.extend(word.take()),
+            _ => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    all.extend(word);
+    all
+}
+
+#[test]
+fn split() {
+    assert_eq!(words("my-copy --stdin"), ["my-copy", "--stdin"]);
+    assert_eq!(
+        words(r#"ssh box  'xclip -o' "a 'b'" ''"#),
+        ["ssh", "box", "xclip -o", "a 'b'", ""]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn commands() {
+    let f = std::env::temp_dir().join(format!("clipp-commands-{}", std::process::id()));
+    let f = f.display();
+    let c = Commands::new(&format!("sh -c 'cat > {f}'"), &format!("cat {f}"));
+    c.copy(Selection::Clipboard, "through sh").unwrap();
+    assert_eq!(c.paste(Selection::Clipboard).unwrap(), "through sh");
+    assert!(c.paste(Selection::Primary).is_err());
+    c.copy_from_reader(Selection::Clipboard, &mut &b"streamed"[..])
+        .unwrap();
+    let mut out = vec![];
+    c.paste_to_writer(Selection::Clipboard, &mut out).unwrap();
+    assert_eq!(out, b"streamed");
+    _ = std::fs::remove_file(f.to_string());
+}
@@ -0,0 +1,93 @@
+//! a shell kept running between calls, for backends where starting one is slow (powershell).
+use super::{timeout, Op};
+use crate::{ClipError, Result};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Instant,
+};
+
+/// printed after each line, so we know it's done
+const DONE: &str = "__clipp_done__";
+
+pub struct Helper {
+    ch: Child,
+    stdin: ChildStdin,
+    lines: mpsc::Receiver<String>,
+}
+
+impl Helper {
+    /// start `c`, which must run each line it's given, i.e. `sh` or `powershell -command -`
+    pub fn start(c: &mut Command) -> Result<Self> {
+        let mut ch = c
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(ClipError::Spawn)?;
+        let stdin = ch.stdin.take().unwrap();
+        let out = BufReader::new(ch.stdout.take().unwrap());
+        let (tx, lines) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("clipp helper".into())
+            .spawn(move || {
+                for l in out.lines().map_while(io::Result::ok) {
+                    if tx.send(l).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(ClipError::Io)?;
+        Ok(Self { ch, stdin, lines })
+    }
+
+    /// run `line`, returning the lines it printed.
+    /// gives up if it takes longer than `op` may, after which the helper is no good.
+    pub fn run(&mut self, line: &str, op: Op) -> Result<Vec<String>> {
+        writeln!(self.stdin, "{line}\necho {DONE}").map_err(ClipError::Io)?;
+        self.stdin.flush().map_err(ClipError::Io)?;
+        let deadline = timeout(op).map(|t| Instant::now() + t);
+        let mut out = vec![];
+        loop {
+            let l = match deadline {
+                Some(d) => self
+                    .lines
+                    .recv_timeout(d.saturating_duration_since(Instant::now()))
+                    .map_err(|e| match e {
+                        RecvTimeoutError::Timeout => ClipError::Timeout,
+                        RecvTimeoutError::Disconnected => gone(),
+                    })?,
+                None => self.lines.recv().map_err(|_| gone())?,
+            };
+            // powershell ends lines with \r
+            if l.trim_end() == DONE {
+                return Ok(out);
+            }
+            out.push(l);
+        }
+    }
+}
+
+fn gone() -> ClipError {
+    ClipError::Io(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "the helper exited",
+    ))
+}
+
+impl Drop for Helper {
+    fn drop(&mut self) {
+        _ = self.ch.kill();
+        _ = self.ch.wait();
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn helper() {
+    let mut h = Helper::start(&mut Command::new("sh")).unwrap();
+    assert_eq!(h.run("echo a; echo b", Op::Paste).unwrap(), ["a", "b"]);
+    assert!(h.run("true", Op::Copy).unwrap().is_empty());
+    h.run("exit", Op::Copy).unwrap_err();
+}
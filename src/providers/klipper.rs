@@ -0,0 +1,70 @@
+//! KDE's klipper, over the session bus, no qdbus needed
+use super::{only_clipboard, text, Provider};
+use crate::{ClipError, Result, Selection};
+use zbus::blocking::{Connection, Proxy};
+
+fn z(e: zbus::Error) -> ClipError {
+    ClipError::Backend(Box::new(e))
+}
+
+pub struct Klipper {
+    klipper: Proxy<'static>,
+}
+
+impl Klipper {
+    /// checks that klipper is running
+    pub fn new() -> Option<Self> {
+        let conn = Connection::session().ok()?;
+        let klipper = Proxy::new(
+            &conn,
+            "org.kde.klipper",
+            "/klipper",
+            "org.kde.klipper.klipper",
+        )
+        .ok()?;
+        let this = Self { klipper };
+        this.contents().ok()?;
+        Some(this)
+    }
+
+    fn contents(&self) -> Result<String> {
+        self.klipper.call("getClipboardContents", &()).map_err(z)
+    }
+
+    pub fn history(&self) -> Result<Vec<String>> {
+        self.klipper.call("getClipboardHistoryMenu", &()).map_err(z)
+    }
+
+    /// klipper gives back nothing past the end
+    pub fn history_item(&self, n: usize) -> Result<Option<String>> {
+        let n = i32::try_from(n).unwrap_or(i32::MAX);
+        let s: String = self
+            .klipper
+            .call("getClipboardHistoryItem", &(n,))
+            .map_err(z)?;
+        Ok((!s.is_empty()).then_some(s))
+    }
+}
+
+impl Provider for Klipper {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        only_clipboard(sel)?;
+        self.klipper
+            .call_method("setClipboardContents", &(text(bytes)?,))
+            .map(drop)
+            .map_err(z)
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        only_clipboard(sel)?;
+        self.contents().map(String::into_bytes)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        only_clipboard(sel)?;
+        self.klipper
+            .call_method("clearClipboardContents", &())
+            .map(drop)
+            .map_err(z)
+    }
+}
@@ -0,0 +1,220 @@
+//! `NSPasteboard`, directly
+use super::{plain, uri, Item, Provider};
+use crate::{ClipError, FileOp, Result, Selection};
+use objc2::{rc::Retained, runtime::ProtocolObject};
+use objc2_app_kit::{NSPasteboard, NSPasteboardItem, NSPasteboardWriting};
+use objc2_foundation::{NSArray, NSData, NSString};
+use std::{
+    path::PathBuf,
+    sync::{atomic::AtomicBool, mpsc, Arc},
+    time::Duration,
+};
+
+/// uniform type identifier for a mime type
+fn uti(mime: &str) -> &str {
+    match mime {
+        m if plain(m) => "public.utf8-plain-text",
+        "text/html" => "public.html",
+        "text/rtf" => "public.rtf",
+        "image/png" => "public.png",
+        "image/tiff" => "public.tiff",
+        "application/pdf" => "com.adobe.pdf",
+        m => m,
+    }
+}
+
+/// the other way around
+fn mime(uti: &str) -> &str {
+    match uti {
+        "public.utf8-plain-text" => "text/plain",
+        "public.html" => "text/html",
+        "public.rtf" => "text/rtf",
+        "public.png" => "image/png",
+        "public.tiff" => "image/tiff",
+        "com.adobe.pdf" => "application/pdf",
+        u => u,
+    }
+}
+
+const FILE_URL: &str = "public.file-url";
+
+#[derive(Default)]
+pub struct Pasteboard {
+    /// a named pasteboard instead of the general one, i.e. the find pasteboard
+    pub name: Option<String>,
+}
+
+impl Pasteboard {
+    fn board(&self, sel: Selection) -> Result<Retained<NSPasteboard>> {
+        super::only_clipboard(sel)?;
+        Ok(match &self.name {
+            Some(n) => NSPasteboard::pasteboardWithName(&NSString::from_str(n)),
+            None => NSPasteboard::generalPasteboard(),
+        })
+    }
+
+    fn write(board: &NSPasteboard, items: &[Retained<NSPasteboardItem>]) -> Result<()> {
+        board.clearContents();
+        let items = items
+            .iter()
+            .map(|i| ProtocolObject::<dyn NSPasteboardWriting>::from_retained(i.clone()))
+            .collect::<Vec<_>>();
+        if board.writeObjects(&NSArray::from_retained_slice(&items)) {
+            Ok(())
+        } else {
+            Err(ClipError::Backend("pasteboard refused the write".into()))
+        }
+    }
+}
+
+impl Provider for Pasteboard {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        self.copy_mime(sel, "text/plain", bytes)
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        Ok(self.paste_mime(sel, "text/plain")?.unwrap_or_default())
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.board(sel)?.clearContents();
+        Ok(())
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        self.copy_formats(sel, &[(mime, bytes)])
+    }
+
+    fn changes(
+        &self,
+        sel: Selection,
+        every: Duration,
+        stop: &Arc<AtomicBool>,
+    ) -> Option<Box<dyn Iterator<Item = ()> + Send>> {
+        // the count is cheap; the data isn't read until it moves
+        let board = Self {
+            name: self.name.clone(),
+        };
+        let mut last = board.change_count(sel).ok()?;
+        let (tx, rx) = mpsc::sync_channel(1);
+        let stop = Arc::clone(stop);
+        std::thread::Builder::new()
+            .name("clipp pasteboard watch".into())
+            .spawn(move || {
+                while crate::watch::nap(every, &stop) {
+                    let Ok(n) = board.change_count(sel) else {
+                        break;
+                    };
+                    if n != last {
+                        last = n;
+                        // a change that's already waiting covers this one
+                        if let Err(mpsc::TrySendError::Disconnected(())) = tx.try_send(()) {
+                            break;
+                        }
+                    }
+                }
+            })
+            .ok()?;
+        Some(Box::new(rx.into_iter()))
+    }
+
+    fn change_count(&self, sel: Selection) -> Result<u64> {
+        Ok(self.board(sel)?.changeCount().cast_unsigned() as u64)
+    }
+
+    fn formats(&self, sel: Selection) -> Result<Vec<String>> {
+        let board = self.board(sel)?;
+        Ok(board
+            .types()
+            .map(|t| t.iter().map(|t| mime(&t.to_string()).to_owned()).collect())
+            .unwrap_or_default())
+    }
+
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        let board = self.board(sel)?;
+        Ok(board
+            .dataForType(&NSString::from_str(uti(mime)))
+            .map(|d| d.to_vec()))
+    }
+
+    fn copy_formats(&self, sel: Selection, formats: &[(&str, &[u8])]) -> Result<()> {
+        self.copy_items(sel, &[formats])
+    }
+
+    fn copy_items(&self, sel: Selection, items: &[&[(&str, &[u8])]]) -> Result<()> {
+        let board = self.board(sel)?;
+        let items = items
+            .iter()
+            .map(|formats| {
+                let item = NSPasteboardItem::new();
+                for &(mime, bytes) in *formats {
+                    item.setData_forType(
+                        &NSData::with_bytes(bytes),
+                        &NSString::from_str(uti(mime)),
+                    );
+                }
+                item
+            })
+            .collect::<Vec<_>>();
+        Self::write(&board, &items)
+    }
+
+    fn paste_items(&self, sel: Selection) -> Result<Vec<Item>> {
+        let board = self.board(sel)?;
+        let Some(items) = board.pasteboardItems() else {
+            return Ok(vec![]);
+        };
+        Ok(items
+            .iter()
+            .map(|i| {
+                i.types()
+                    .iter()
+                    .filter_map(|t| {
+                        let data = i.dataForType(&t)?;
+                        Some((mime(&t.to_string()).to_owned(), data.to_vec()))
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn copy_files(&self, sel: Selection, paths: &[PathBuf]) -> Result<()> {
+        let board = self.board(sel)?;
+        let items = paths
+            .iter()
+            .map(|p| {
+                let p = std::path::absolute(p).map_err(ClipError::Io)?;
+                let item = NSPasteboardItem::new();
+                item.setString_forType(
+                    &NSString::from_str(&uri::encode(&p)),
+                    &NSString::from_str(FILE_URL),
+                );
+                Ok(item)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::write(&board, &items)
+    }
+
+    fn copy_files_op(&self, sel: Selection, paths: &[PathBuf], op: FileOp) -> Result<()> {
+        match op {
+            FileOp::Copy => self.copy_files(sel, paths),
+            // finder moves on the paste side, with option held
+            FileOp::Cut => Err(ClipError::Unsupported),
+        }
+    }
+
+    fn paste_files(&self, sel: Selection) -> Result<Vec<PathBuf>> {
+        let board = self.board(sel)?;
+        let ty = NSString::from_str(FILE_URL);
+        Ok(board
+            .pasteboardItems()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|i| i.stringForType(&ty))
+                    .filter_map(|u| uri::decode(&u.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,174 @@
+//! the history that clipboard managers keep: cliphist and clipman on wlroots, greenclip on x11
+use super::{has, Eat};
+use crate::{ClipError, Result};
+use std::{path::PathBuf, process::Command};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Manager {
+    Cliphist,
+    Clipman,
+    Greenclip,
+}
+
+/// Something a clipboard history manager (cliphist, clipman, or greenclip) kept.
+/// See [`manager_history`](crate::manager_history).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stored {
+    /// cliphist's id for it, or where it is in clipman's or greenclip's history, 0 being the newest.
+    pub id: u64,
+    /// What it looks like. cliphist cuts it short, and describes images;
+    /// greenclip puts it on one line; clipman keeps all of it.
+    pub preview: String,
+    by: Manager,
+}
+
+impl Stored {
+    /// What was copied. greenclip can't give it back, only [`restore`](Stored::restore) it, so this is the preview.
+    ///
+    /// # Errors
+    ///
+    /// If cliphist fails, i.e. the entry was deleted since.
+    pub fn data(&self) -> Result<Vec<u8>> {
+        match self.by {
+            Manager::Cliphist => Command::new("cliphist")
+                .arg("decode")
+                .arg(self.id.to_string())
+                .eat(),
+            Manager::Clipman | Manager::Greenclip => Ok(self.preview.clone().into_bytes()),
+        }
+    }
+
+    /// Copy it back to the clipboard.
+    ///
+    /// # Errors
+    ///
+    /// If it can't be read back, no clipboard is available, or copying fails.
+    pub fn restore(&self) -> Result<()> {
+        if self.by == Manager::Greenclip {
+            // what rofi does with the line it was given
+            return Command::new("greenclip")
+                .arg("print")
+                .arg(&self.preview)
+                .eat()
+                .map(drop);
+        }
+        crate::current()?.copy_bytes(&self.data()?)
+    }
+}
+
+/// where clipman keeps its history
+fn clipman_file() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))
+        .map(|d| d.join("clipman.json"))
+}
+
+/// newest first
+pub fn stored() -> Result<Vec<Stored>> {
+    if has("cliphist") {
+        let list = Command::new("cliphist").arg("list").eat()?;
+        return Ok(String::from_utf8_lossy(&list)
+            .lines()
+            .filter_map(|l| {
+                let (id, preview) = l.split_once('\t')?;
+                Some(Stored {
+                    id: id.parse().ok()?,
+                    preview: preview.to_owned(),
+                    by: Manager::Cliphist,
+                })
+            })
+            .collect());
+    }
+    if let Some(file) = clipman_file().filter(|f| has("clipman") && f.exists()) {
+        return clipman(&std::fs::read_to_string(file).map_err(ClipError::Io)?);
+    }
+    if has("greenclip") {
+        let list = Command::new("greenclip").arg("print").eat()?;
+        return Ok((0..)
+            .zip(String::from_utf8_lossy(&list).lines())
+            .map(|(id, l)| Stored {
+                id,
+                preview: l.to_owned(),
+                by: Manager::Greenclip,
+            })
+            .collect());
+    }
+    Err(ClipError::NoBackend)
+}
+
+fn clipman(json: &str) -> Result<Vec<Stored>> {
+    // oldest first
+    let mut all = strings(json).ok_or(ClipError::Unsupported)?;
+    all.reverse();
+    Ok((0..)
+        .zip(all)
+        .map(|(id, preview)| Stored {
+            id,
+            preview,
+            by: Manager::Clipman,
+        })
+        .collect())
+}
+
+/// a json array of strings, which is all clipman writes
+fn strings(json: &str) -> Option<Vec<String>> {
+    let mut c = json.trim().strip_prefix('[')?.chars();
+    let mut all = vec![];
+    loop {
+        match c.find(|c| !c.is_whitespace())? {
+            ']' => return Some(all),
+            ',' => {}
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match c.next()? {
+                        '"' => break,
+                        '\\' => s.push(match c.next()? {
+                            'n' => '\n',
+                            't' => '\t',
+                            'r' => '\r',
+                            'b' => '\u{8}',
+                            'f' => '\u{c}',
+                            'u' => {
+                                let n = hex(&mut c)?;
+                                let n = if (0xd800..0xdc00).contains(&n) {
+                                    // the other half, after a \u
+                                    let (Some('\\'), Some('u')) = (c.next(), c.next()) else {
+                                        return None;
+                                    };
+                                    0x10000
+                                        + ((n - 0xd800) << 10)
+                                        + hex(&mut c)?.checked_sub(0xdc00)?
+                                } else {
+                                    n
+                                };
+                                char::from_u32(n)?
+                            }
+                            e => e,
+                        }),
+                        ch => s.push(ch),
+                    }
+                }
+                all.push(s);
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// the four digits after a `\u`
+fn hex(c: &mut impl Iterator<Item = char>) -> Option<u32> {
+    let h: String = c.take(4).collect();
+    u32::from_str_radix(&h, 16).ok()
+}
+
+#[test]
+fn clipman_json() {
+    assert_eq!(
+        strings(r#"["one", "two\nlines", "\"q\" é \ud83d\ude00"]"#).unwrap(),
+        ["one", "two\nlines", "\"q\" é 😀"]
+    );
+    assert_eq!(strings("[]").unwrap(), Vec::<String>::new());
+    assert_eq!(strings(r#"["open"#), None);
+}
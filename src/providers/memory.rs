@@ -0,0 +1,119 @@
+//! a clipboard that only lives in this process
+use super::{plain, Provider};
+use crate::{Lazy, Result, Selection};
+use std::{collections::HashMap, sync::Mutex};
+
+/// what a selection holds, by mime type
+type Formats = Vec<(String, Lazy)>;
+
+#[derive(Default)]
+pub struct Memory {
+    held: Mutex<HashMap<Selection, Formats>>,
+}
+
+impl Memory {
+    fn get(&self, sel: Selection, mime: &str) -> Option<Vec<u8>> {
+        self.with(sel, mime, <[u8]>::to_vec)
+    }
+
+    fn with<T>(&self, sel: Selection, mime: &str, f: impl FnOnce(&[u8]) -> T) -> Option<T> {
+        let data = {
+            let held = self.held.lock().unwrap();
+            let found = held
+                .get(&sel)?
+                .iter()
+                .find(|(m, _)| m == mime || plain(m) && plain(mime));
+            found?.1.clone()
+        };
+        Some(f(&data.get()))
+    }
+}
+
+impl Provider for Memory {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        self.copy_formats(sel, &[("text/plain", bytes)])
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        Ok(self.get(sel, "text/plain").unwrap_or_default())
+    }
+
+    fn paste_bytes_into(&self, sel: Selection, buf: &mut Vec<u8>) -> Result<()> {
+        self.with(sel, "text/plain", |b| buf.extend_from_slice(b));
+        Ok(())
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.held.lock().unwrap().remove(&sel);
+        Ok(())
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        self.copy_formats(sel, &[(mime, bytes)])
+    }
+
+    fn formats(&self, sel: Selection) -> Result<Vec<String>> {
+        let held = self.held.lock().unwrap();
+        Ok(held
+            .get(&sel)
+            .map(|f| f.iter().map(|(m, _)| m.clone()).collect())
+            .unwrap_or_default())
+    }
+
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.get(sel, mime))
+    }
+
+    fn size(&self, sel: Selection, mime: &str) -> Result<Option<usize>> {
+        Ok(self.with(sel, mime, <[u8]>::len))
+    }
+
+    fn copy_formats(&self, sel: Selection, formats: &[(&str, &[u8])]) -> Result<()> {
+        let f = formats
+            .iter()
+            .map(|&(m, b)| (m.to_owned(), Lazy::made(b.into())))
+            .collect();
+        self.held.lock().unwrap().insert(sel, f);
+        Ok(())
+    }
+
+    fn copy_lazy(&self, sel: Selection, mime: &str, data: Lazy) -> Result<()> {
+        let f = vec![(mime.to_owned(), data)];
+        self.held.lock().unwrap().insert(sel, f);
+        Ok(())
+    }
+}
+
+#[test]
+fn memory() {
+    let m = Memory::default();
+    m.copy(Selection::Clipboard, "text").unwrap();
+    assert_eq!(m.paste(Selection::Clipboard).unwrap(), "text");
+    assert_eq!(m.paste(Selection::Primary).unwrap(), "");
+    m.copy_formats(
+        Selection::Clipboard,
+        &[("text/html", b"<b>hi</b>"), ("text/plain", b"hi")],
+    )
+    .unwrap();
+    assert_eq!(
+        m.paste_mime(Selection::Clipboard, "text/html")
+            .unwrap()
+            .unwrap(),
+        b"<b>hi</b>"
+    );
+    assert_eq!(m.paste(Selection::Clipboard).unwrap(), "hi");
+    assert_eq!(
+        m.paste_mime(Selection::Clipboard, "image/png").unwrap(),
+        None
+    );
+    assert_eq!(
+        m.formats(Selection::Clipboard).unwrap(),
+        ["text/html", "text/plain"]
+    );
+    assert_eq!(m.size(Selection::Clipboard, "text/html").unwrap(), Some(9));
+    m.clear(Selection::Clipboard).unwrap();
+    assert_eq!(
+        m.paste_mime(Selection::Clipboard, "text/html").unwrap(),
+        None
+    );
+}
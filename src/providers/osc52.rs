@@ -0,0 +1,53 @@
+//! copies by asking the terminal to, with the OSC 52 escape sequence.
+//! works over ssh, but the terminal can't be asked for the clipboard back.
+use super::{base64, Provider};
+use crate::{ClipError, Result, Selection};
+use std::{fs::File, io::Write};
+
+pub struct Osc52 {}
+
+impl Osc52 {
+    /// is there a terminal to talk to
+    pub fn available() -> bool {
+        tty().is_ok() && std::env::var("TERM").is_ok_and(|t| t != "dumb")
+    }
+
+    /// ssh doesn't always pass `TERM` along, but there's a terminal on the other end all the same
+    pub fn over_ssh() -> bool {
+        tty().is_ok() && !std::env::var("TERM").is_ok_and(|t| t == "dumb")
+    }
+
+    fn send(sel: Selection, data: &str) -> Result<()> {
+        let sel = match sel {
+            Selection::Clipboard => 'c',
+            Selection::Primary => 'p',
+        };
+        let mut seq = format!("\x1b]52;{sel};{data}\x07");
+        // tmux eats it unless its passed through
+        if std::env::var("TMUX").is_ok() {
+            seq = format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"));
+        }
+        let mut t = tty().map_err(ClipError::Io)?;
+        t.write_all(seq.as_bytes()).map_err(ClipError::Io)?;
+        t.flush().map_err(ClipError::Io)
+    }
+}
+
+fn tty() -> std::io::Result<File> {
+    File::options().write(true).open("/dev/tty")
+}
+
+impl Provider for Osc52 {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        Self::send(sel, &base64::encode(bytes))
+    }
+
+    fn paste_bytes(&self, _sel: Selection) -> Result<Vec<u8>> {
+        Err(ClipError::Unsupported)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        // not base64, so the terminal clears it
+        Self::send(sel, "!")
+    }
+}
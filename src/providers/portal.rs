@@ -0,0 +1,298 @@
+//! the xdg desktop portal clipboard, for flatpak and snap sandboxes.
+//! it only comes with a remote desktop session, so the user gets asked once.
+use super::{only_clipboard, plain, Provider};
+use crate::{ClipError, Lazy, Result, Selection};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::Duration,
+};
+use zbus::{
+    blocking::{Connection, Proxy},
+    zvariant::{OwnedFd, OwnedObjectPath, OwnedValue, Value},
+};
+
+const DEST: &str = "org.freedesktop.portal.Desktop";
+const PATH: &str = "/org/freedesktop/portal/desktop";
+/// what text goes by
+const TEXT: [&str; 3] = ["text/plain;charset=utf-8", "text/plain", "UTF8_STRING"];
+
+trait Z<T> {
+    fn z(self) -> Result<T>;
+}
+
+impl<T, E: std::error::Error + Send + Sync + 'static> Z<T> for std::result::Result<T, E> {
+    fn z(self) -> Result<T> {
+        self.map_err(|e| ClipError::Backend(Box::new(e)))
+    }
+}
+
+/// what we offer, by mime type
+type Offers = HashMap<String, Lazy>;
+
+pub struct Portal {
+    clipboard: Proxy<'static>,
+    session: OwnedObjectPath,
+    offers: Arc<Mutex<Offers>>,
+}
+
+/// are we in a sandbox
+pub fn sandboxed() -> bool {
+    std::path::Path::new("/.flatpak-info").exists() || std::env::var("SNAP").is_ok()
+}
+
+/// the results of a portal request, after the user answered it
+fn request(
+    conn: &Connection,
+    method: &str,
+    call: impl FnOnce(HashMap<&str, Value<'_>>) -> Result<()>,
+) -> Result<HashMap<String, OwnedValue>> {
+    static N: AtomicU32 = AtomicU32::new(0);
+    let token = format!("clipp{}", N.fetch_add(1, Ordering::Relaxed));
+    let sender = conn
+        .unique_name()
+        .ok_or_else(|| ClipError::Backend("not on the bus".into()))?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    // listen before asking, so the response cant be missed
+    let req = Proxy::new(
+        conn,
+        DEST,
+        format!("{PATH}/request/{sender}/{token}"),
+        "org.freedesktop.portal.Request",
+    )
+    .z()?;
+    let mut responses = req.receive_signal("Response").z()?;
+    call(HashMap::from([("handle_token", Value::from(token))]))?;
+    let r = responses
+        .next()
+        .ok_or_else(|| ClipError::Backend("portal went away".into()))?;
+    let (code, results) = r
+        .body()
+        .deserialize::<(u32, HashMap<String, OwnedValue>)>()
+        .z()?;
+    if code != 0 {
+        return Err(ClipError::Backend(
+            format!("portal {method} was denied").into(),
+        ));
+    }
+    Ok(results)
+}
+
+impl Portal {
+    pub fn new() -> Option<Self> {
+        Self::start().ok()
+    }
+
+    fn start() -> Result<Self> {
+        let conn = Connection::session().z()?;
+        let desktop = Proxy::new(&conn, DEST, PATH, "org.freedesktop.portal.RemoteDesktop").z()?;
+        let clipboard = Proxy::new(&conn, DEST, PATH, "org.freedesktop.portal.Clipboard").z()?;
+        let r = request(&conn, "CreateSession", |mut o| {
+            o.insert("session_handle_token", Value::from("clipp"));
+            desktop.call_method("CreateSession", &(o,)).z().map(drop)
+        })?;
+        let session = r
+            .get("session_handle")
+            .and_then(|s| String::try_from(s.try_clone().ok()?).ok())
+            .and_then(|s| OwnedObjectPath::try_from(s).ok())
+            .ok_or_else(|| ClipError::Backend("portal gave no session".into()))?;
+        clipboard
+            .call_method(
+                "RequestClipboard",
+                &(&session, HashMap::<&str, Value<'_>>::new()),
+            )
+            .z()?;
+        let r = request(&conn, "Start", |o| {
+            desktop
+                .call_method("Start", &(&session, "", o))
+                .z()
+                .map(drop)
+        })?;
+        if !r
+            .get("clipboard_enabled")
+            .is_some_and(|e| bool::try_from(e).unwrap_or(false))
+        {
+            return Err(ClipError::Unsupported);
+        }
+        let offers = Arc::<Mutex<Offers>>::default();
+        let transfers = clipboard.receive_signal("SelectionTransfer").z()?;
+        let (c, s, o) = (clipboard.clone(), session.clone(), Arc::clone(&offers));
+        std::thread::Builder::new()
+            .name("clipp portal".into())
+            .spawn(move || {
+                for t in transfers {
+                    if let Ok((_, mime, serial)) =
+                        t.body().deserialize::<(OwnedObjectPath, String, u32)>()
+                    {
+                        // lazy data is made with the lock let go
+                        let data = o.lock().unwrap().get(&mime).cloned();
+                        _ = serve(&c, &s, serial, data.map(|d| d.get()).as_deref());
+                    }
+                }
+            })
+            .map_err(ClipError::Io)?;
+        Ok(Self {
+            clipboard,
+            session,
+            offers,
+        })
+    }
+
+    /// offer `formats` as the selection
+    fn own(&self, formats: Offers) -> Result<()> {
+        let types = formats.keys().cloned().collect::<Vec<_>>();
+        let o = HashMap::from([("mime_types", Value::from(types))]);
+        // first, as a transfer can be asked for as soon as it's set
+        let was = std::mem::replace(&mut *self.offers.lock().unwrap(), formats);
+        if let Err(e) = self
+            .clipboard
+            .call_method("SetSelection", &(&self.session, o))
+            .z()
+        {
+            *self.offers.lock().unwrap() = was;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn read(&self, mime: &str) -> Result<Option<Vec<u8>>> {
+        let fd = match self
+            .clipboard
+            .call::<_, _, OwnedFd>("SelectionRead", &(&self.session, mime))
+        {
+            // nobody has it
+            Err(zbus::Error::MethodError(..)) => return Ok(None),
+            fd => fd.z()?,
+        };
+        let mut b = vec![];
+        File::from(std::os::fd::OwnedFd::from(fd))
+            .read_to_end(&mut b)
+            .map_err(ClipError::Io)?;
+        Ok(Some(b))
+    }
+}
+
+/// hand over what was asked for, if we have it
+fn serve(
+    clipboard: &Proxy<'_>,
+    session: &OwnedObjectPath,
+    serial: u32,
+    data: Option<&[u8]>,
+) -> Result<()> {
+    let ok = match data {
+        Some(d) => {
+            let fd = clipboard
+                .call::<_, _, OwnedFd>("SelectionWrite", &(session, serial))
+                .z()?;
+            File::from(std::os::fd::OwnedFd::from(fd))
+                .write_all(d)
+                .is_ok()
+        }
+        None => false,
+    };
+    clipboard
+        .call_method("SelectionWriteDone", &(session, serial, ok))
+        .z()?;
+    Ok(())
+}
+
+impl Provider for Portal {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        self.copy_formats(sel, &[("text/plain", bytes)])
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        only_clipboard(sel)?;
+        for t in TEXT {
+            if let Some(b) = self.read(t)? {
+                return Ok(b);
+            }
+        }
+        Ok(vec![])
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        only_clipboard(sel)?;
+        self.own(Offers::new())
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        self.copy_formats(sel, &[(mime, bytes)])
+    }
+
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        if plain(mime) {
+            return self.paste_bytes(sel).map(Some);
+        }
+        only_clipboard(sel)?;
+        self.read(mime)
+    }
+
+    fn copy_formats(&self, sel: Selection, formats: &[(&str, &[u8])]) -> Result<()> {
+        only_clipboard(sel)?;
+        let mut offers = Offers::new();
+        for &(mime, bytes) in formats {
+            let b = Lazy::made(bytes.into());
+            if plain(mime) {
+                offers.extend(TEXT.map(|t| (t.to_owned(), b.clone())));
+            } else {
+                offers.insert(mime.to_owned(), b);
+            }
+        }
+        self.own(offers)
+    }
+
+    fn copy_lazy(&self, sel: Selection, mime: &str, data: Lazy) -> Result<()> {
+        only_clipboard(sel)?;
+        let offers = if plain(mime) {
+            TEXT.map(|t| (t.to_owned(), data.clone())).into()
+        } else {
+            Offers::from([(mime.to_owned(), data)])
+        };
+        self.own(offers)
+    }
+
+    fn changes(
+        &self,
+        sel: Selection,
+        _every: Duration,
+        stop: &Arc<AtomicBool>,
+    ) -> Option<Box<dyn Iterator<Item = ()> + Send>> {
+        only_clipboard(sel).ok()?;
+        let signals = self
+            .clipboard
+            .receive_signal("SelectionOwnerChanged")
+            .ok()?;
+        // waiting for a signal can't be interrupted, so it's waited for on the side.
+        // that thread sleeps until the next one, and then finds nobody listening
+        let (tx, rx) = mpsc::sync_channel(1);
+        std::thread::Builder::new()
+            .name("clipp portal watch".into())
+            .spawn(move || {
+                for _ in signals {
+                    // a change that's already waiting covers this one
+                    if let Err(mpsc::TrySendError::Disconnected(())) = tx.try_send(()) {
+                        break;
+                    }
+                }
+            })
+            .ok()?;
+        let stop = Arc::clone(stop);
+        Some(Box::new(std::iter::from_fn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                match rx.recv_timeout(Duration::from_millis(50)) {
+                    Ok(()) => return Some(()),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+                }
+            }
+            None
+        })))
+    }
+}
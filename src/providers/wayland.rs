@@ -0,0 +1,126 @@
+//! in process wayland clipboard, over the data control protocol
+use super::{plain, Provider};
+use crate::{ClipError, Result, Selection};
+use std::io::Read;
+use wl_clipboard_rs::{
+    copy::{self, MimeSource, Options, Source},
+    paste::{self, get_contents, get_mime_types, Error},
+};
+
+fn backend(e: impl std::error::Error + Send + Sync + 'static) -> ClipError {
+    ClipError::Backend(Box::new(e))
+}
+
+pub struct WlClipboard {}
+
+impl WlClipboard {
+    /// checks that the compositor does data control
+    pub fn new() -> Option<Self> {
+        match get_mime_types(paste::ClipboardType::Regular, paste::Seat::Unspecified) {
+            Ok(_) | Err(Error::ClipboardEmpty | Error::NoMimeType) => Some(Self {}),
+            Err(_) => None,
+        }
+    }
+
+    fn options(sel: Selection) -> Options {
+        let mut o = Options::new();
+        o.clipboard(match sel {
+            Selection::Clipboard => copy::ClipboardType::Regular,
+            Selection::Primary => copy::ClipboardType::Primary,
+        });
+        if let Some(s) = super::seat() {
+            o.seat(copy::Seat::Specific(s));
+        }
+        o
+    }
+
+    /// the seat [`set_seat`](super::set_seat) picked, to paste from
+    fn seat(seat: Option<&str>) -> paste::Seat<'_> {
+        seat.map_or(paste::Seat::Unspecified, paste::Seat::Specific)
+    }
+
+    fn ty(sel: Selection) -> paste::ClipboardType {
+        match sel {
+            Selection::Clipboard => paste::ClipboardType::Regular,
+            Selection::Primary => paste::ClipboardType::Primary,
+        }
+    }
+
+    fn mime(mime: &str) -> copy::MimeType {
+        if plain(mime) {
+            copy::MimeType::Text
+        } else {
+            copy::MimeType::Specific(mime.to_owned())
+        }
+    }
+
+    /// [`None`] if theres nothing in `mime`
+    fn get(sel: Selection, mime: paste::MimeType) -> Result<Option<Vec<u8>>> {
+        let seat = super::seat();
+        match get_contents(Self::ty(sel), Self::seat(seat.as_deref()), mime) {
+            Ok((mut pipe, _)) => {
+                let mut v = vec![];
+                pipe.read_to_end(&mut v).map_err(ClipError::Io)?;
+                Ok(Some(v))
+            }
+            Err(Error::NoSeats | Error::ClipboardEmpty | Error::NoMimeType) => Ok(None),
+            Err(Error::PrimarySelectionUnsupported) => Err(ClipError::Unsupported),
+            Err(e) => Err(backend(e)),
+        }
+    }
+}
+
+impl Provider for WlClipboard {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        Self::options(sel)
+            .copy(Source::Bytes(bytes.into()), copy::MimeType::Text)
+            .map_err(backend)
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        Ok(Self::get(sel, paste::MimeType::Text)?.unwrap_or_default())
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        let ty = match sel {
+            Selection::Clipboard => copy::ClipboardType::Regular,
+            Selection::Primary => copy::ClipboardType::Primary,
+        };
+        let seat = super::seat().map_or(copy::Seat::All, copy::Seat::Specific);
+        copy::clear(ty, seat).map_err(backend)
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        Self::options(sel)
+            .copy(Source::Bytes(bytes.into()), Self::mime(mime))
+            .map_err(backend)
+    }
+
+    fn formats(&self, sel: Selection) -> Result<Vec<String>> {
+        let seat = super::seat();
+        match get_mime_types(Self::ty(sel), Self::seat(seat.as_deref())) {
+            Ok(t) => Ok(t.into_iter().collect()),
+            Err(Error::NoSeats | Error::ClipboardEmpty | Error::NoMimeType) => Ok(vec![]),
+            Err(Error::PrimarySelectionUnsupported) => Err(ClipError::Unsupported),
+            Err(e) => Err(backend(e)),
+        }
+    }
+
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        if plain(mime) {
+            return Self::get(sel, paste::MimeType::Text);
+        }
+        Self::get(sel, paste::MimeType::Specific(mime))
+    }
+
+    fn copy_formats(&self, sel: Selection, formats: &[(&str, &[u8])]) -> Result<()> {
+        let sources = formats
+            .iter()
+            .map(|&(mime, bytes)| MimeSource {
+                source: Source::Bytes(bytes.into()),
+                mime_type: Self::mime(mime),
+            })
+            .collect();
+        Self::options(sel).copy_multi(sources).map_err(backend)
+    }
+}
@@ -0,0 +1,84 @@
+//! the browser's clipboard, through `navigator.clipboard`.
+//! it only answers asynchronously, so pasting is left to [`paste_async`],
+//! and copying without it doesn't find out if it worked.
+use super::{only_clipboard, Provider};
+use crate::{
+    text::{endings, endings_owned},
+    ClipError, Op, Result, Selection,
+};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+fn js(e: &JsValue) -> ClipError {
+    ClipError::Backend(format!("{e:?}").into())
+}
+
+fn clipboard() -> Result<web_sys::Clipboard> {
+    Ok(web_sys::window()
+        .ok_or(ClipError::NoBackend)?
+        .navigator()
+        .clipboard())
+}
+
+pub struct Web {}
+
+impl Web {
+    /// checks there's a window, i.e. this isn't a worker
+    pub fn new() -> Option<Self> {
+        web_sys::window().map(|_| Self {})
+    }
+}
+
+impl Provider for Web {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        only_clipboard(sel)?;
+        let text = String::from_utf8(bytes.to_vec()).map_err(ClipError::Utf8)?;
+        let p = clipboard()?.write_text(&text);
+        spawn_local(async {
+            _ = JsFuture::from(p).await;
+        });
+        Ok(())
+    }
+
+    fn paste_bytes(&self, _sel: Selection) -> Result<Vec<u8>> {
+        Err(ClipError::Unsupported)
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.copy_bytes(sel, b"")
+    }
+}
+
+/// Copy text to the clipboard, waiting for the browser to say it worked.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the browser refuses, i.e. the page isn't focused.
+pub async fn copy_async(text: &str) -> Result<()> {
+    let c = crate::current()?;
+    if c.backend_name() != "web" {
+        return c.copy(text);
+    }
+    JsFuture::from(clipboard()?.write_text(&endings(Op::Copy, text)))
+        .await
+        .map(drop)
+        .map_err(|e| js(&e))
+}
+
+/// Paste text from the clipboard. The browser may ask the user first.
+///
+/// # Errors
+///
+/// If no clipboard is available, or the browser refuses.
+pub async fn paste_async() -> Result<String> {
+    let c = crate::current()?;
+    if c.backend_name() != "web" {
+        return c.paste();
+    }
+    JsFuture::from(clipboard()?.read_text())
+        .await
+        .map_err(|e| js(&e))?
+        .as_string()
+        .map(|s| endings_owned(Op::Paste, s))
+        .ok_or(ClipError::Unsupported)
+}
@@ -0,0 +1,49 @@
+//! windows' clipboard history, the one win+v shows, through winrt
+use crate::{ClipError, Result};
+use windows::ApplicationModel::DataTransfer::{
+    Clipboard, ClipboardHistoryItem, ClipboardHistoryItemsResultStatus,
+    SetHistoryItemAsContentStatus, StandardDataFormats,
+};
+
+fn w(e: windows::core::Error) -> ClipError {
+    ClipError::Backend(Box::new(e))
+}
+
+/// the items with text, newest first, and their text
+fn items() -> Result<Vec<(ClipboardHistoryItem, String)>> {
+    let r = Clipboard::GetHistoryItemsAsync()
+        .map_err(w)?
+        .join()
+        .map_err(w)?;
+    // history is off, or group policy says no
+    if r.Status().map_err(w)? != ClipboardHistoryItemsResultStatus::Success {
+        return Err(ClipError::Unsupported);
+    }
+    let text = StandardDataFormats::Text().map_err(w)?;
+    let mut all = vec![];
+    for item in r.Items().map_err(w)? {
+        let content = item.Content().map_err(w)?;
+        if content.Contains(&text).map_err(w)? {
+            let t = content.GetTextAsync().map_err(w)?.join().map_err(w)?;
+            all.push((item, t.to_string_lossy()));
+        }
+    }
+    Ok(all)
+}
+
+pub fn history() -> Result<Vec<String>> {
+    Ok(items()?.into_iter().map(|(_, t)| t).collect())
+}
+
+/// false past the end
+pub fn restore(n: usize) -> Result<bool> {
+    let Some((item, _)) = items()?.into_iter().nth(n) else {
+        return Ok(false);
+    };
+    match Clipboard::SetHistoryItemAsContent(&item).map_err(w)? {
+        SetHistoryItemAsContentStatus::Success => Ok(true),
+        // it was deleted since
+        SetHistoryItemAsContentStatus::ItemDeleted => Ok(false),
+        _ => Err(ClipError::Unsupported),
+    }
+}
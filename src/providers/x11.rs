@@ -0,0 +1,561 @@
+//! talks the x11 selection protocol directly, no xclip needed
+use super::{plain, Provider};
+use crate::{ClipError, Lazy, Result, Selection};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use x11rb::{
+    connection::{Connection, RequestConnection as _},
+    protocol::{
+        xfixes::{ConnectionExt as _, SelectionEventMask},
+        xproto::{
+            Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt as _, CreateWindowAux,
+            EventMask, PropMode, Property, PropertyNotifyEvent, SelectionNotifyEvent,
+            SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, CURRENT_TIME, NONE,
+};
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        CLIPBOARD,
+        TARGETS,
+        UTF8_STRING,
+        TEXT,
+        INCR,
+        CLIPP,
+        TEXT_PLAIN: b"text/plain;charset=utf-8",
+    }
+}
+
+/// how long a selection owner gets to answer
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+trait X<T> {
+    fn x(self) -> Result<T>;
+}
+
+impl<T, E: std::error::Error + Send + Sync + 'static> X<T> for std::result::Result<T, E> {
+    fn x(self) -> Result<T> {
+        self.map_err(|e| ClipError::Backend(Box::new(e)))
+    }
+}
+
+/// what we offer, per selection
+type Offers = HashMap<Atom, Vec<(Atom, Lazy)>>;
+
+struct Conn {
+    conn: RustConnection,
+    win: Window,
+}
+
+impl Conn {
+    fn new(display: Option<&str>) -> Result<Self> {
+        let (conn, screen) = x11rb::connect(display).x()?;
+        let root = conn.setup().roots[screen].root;
+        let win = conn.generate_id().x()?;
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            win,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )
+        .x()?;
+        conn.flush().x()?;
+        Ok(Self { conn, win })
+    }
+}
+
+pub struct X11 {
+    /// owns our selections, served from a thread
+    server: Arc<Conn>,
+    offers: Arc<Mutex<Offers>>,
+    /// pastes happen here, so the server thread doesnt eat the notifications
+    paster: Mutex<Conn>,
+    atoms: Atoms,
+    /// instead of `DISPLAY`
+    display: Option<String>,
+}
+
+impl X11 {
+    /// on `display`, or `DISPLAY`
+    pub fn new(display: Option<&str>) -> Option<Self> {
+        let server = Arc::new(Conn::new(display).ok()?);
+        let atoms = Atoms::new(&server.conn).ok()?.reply().ok()?;
+        let offers = Arc::default();
+        let this = Self {
+            paster: Mutex::new(Conn::new(display).ok()?),
+            server: Arc::clone(&server),
+            offers: Arc::clone(&offers),
+            atoms,
+            display: display.map(str::to_owned),
+        };
+        std::thread::Builder::new()
+            .name("clipp x11".into())
+            .spawn(move || serve(&server, &offers, atoms))
+            .ok()?;
+        Some(this)
+    }
+
+    fn atom(&self, sel: Selection) -> Atom {
+        match sel {
+            Selection::Clipboard => self.atoms.CLIPBOARD,
+            Selection::Primary => AtomEnum::PRIMARY.into(),
+        }
+    }
+
+    fn intern(&self, name: &str) -> Result<Atom> {
+        Ok(self
+            .server
+            .conn
+            .intern_atom(false, name.as_bytes())
+            .x()?
+            .reply()
+            .x()?
+            .atom)
+    }
+
+    /// the targets text is offered as
+    fn text(&self) -> [Atom; 4] {
+        [
+            self.atoms.UTF8_STRING,
+            self.atoms.TEXT_PLAIN,
+            AtomEnum::STRING.into(),
+            self.atoms.TEXT,
+        ]
+    }
+
+    /// take ownership of `sel`, offering `targets`
+    fn own(&self, sel: Selection, targets: Vec<(Atom, Lazy)>) -> Result<()> {
+        let sel = self.atom(sel);
+        self.offers.lock().unwrap().insert(sel, targets);
+        let Conn { conn, win } = &*self.server;
+        conn.set_selection_owner(*win, sel, CURRENT_TIME).x()?;
+        conn.flush().x()?;
+        if !self.owns(sel)? {
+            self.offers.lock().unwrap().remove(&sel);
+            return Err(ClipError::Backend("couldn't take the selection".into()));
+        }
+        Ok(())
+    }
+
+    fn owns(&self, sel: Atom) -> Result<bool> {
+        let Conn { conn, win } = &*self.server;
+        Ok(conn.get_selection_owner(sel).x()?.reply().x()?.owner == *win)
+    }
+
+    /// what we're offering as `target`
+    fn offered(&self, sel: Atom, target: Atom) -> Option<Vec<u8>> {
+        let offers = self.offers.lock().unwrap().get(&sel)?.clone();
+        if target == self.atoms.TARGETS {
+            let atoms = offers.iter().map(|&(a, _)| a).chain([self.atoms.TARGETS]);
+            return Some(atoms.flat_map(u32::to_ne_bytes).collect());
+        }
+        offers
+            .iter()
+            .find(|&&(a, _)| a == target)
+            .map(|(_, d)| d.get().to_vec())
+    }
+
+    /// ask the selection owner for `target`. [`None`] if it doesnt have it.
+    fn convert(&self, sel: Selection, target: Atom) -> Result<Option<Vec<u8>>> {
+        let sel = self.atom(sel);
+        if self.owns(sel)? {
+            return Ok(self.offered(sel, target));
+        }
+        self.ask(sel, target, |conn, win, prop| {
+            let r = conn
+                .get_property(true, win, prop, AtomEnum::ANY, 0, u32::MAX)
+                .x()?
+                .reply()
+                .x()?;
+            if r.type_ == self.atoms.INCR {
+                // deleting it asked for the first piece
+                return incr(conn, win, prop);
+            }
+            Ok(r.value)
+        })
+    }
+
+    /// have someone else's `sel` put `target` on our window, and `read` it from the property there
+    fn ask<T>(
+        &self,
+        sel: Atom,
+        target: Atom,
+        read: impl FnOnce(&RustConnection, Window, Atom) -> Result<T>,
+    ) -> Result<Option<T>> {
+        let p = self.paster.lock().unwrap();
+        let Conn { conn, win } = &*p;
+        let prop = self.atoms.CLIPP;
+        conn.convert_selection(*win, sel, target, prop, CURRENT_TIME)
+            .x()?;
+        conn.flush().x()?;
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            match conn.poll_for_event().x()? {
+                Some(Event::SelectionNotify(e)) if e.requestor == *win => {
+                    if e.property == NONE {
+                        return Ok(None);
+                    }
+                    return read(conn, *win, prop).map(Some);
+                }
+                Some(_) => {}
+                None if Instant::now() > deadline => {
+                    return Err(ClipError::Backend("selection owner didn't respond".into()))
+                }
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+    }
+
+    fn targets(&self, sel: Selection) -> Result<Vec<Atom>> {
+        Ok(self
+            .convert(sel, self.atoms.TARGETS)?
+            .map(|t| {
+                t.chunks_exact(4)
+                    .map(|c| u32::from_ne_bytes(c.try_into().unwrap()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+/// the rest of an INCR transfer: the owner puts each piece in `prop` once we delete the last,
+/// and ends with an empty one
+fn incr(conn: &RustConnection, win: Window, prop: Atom) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    let mut deadline = Instant::now() + TIMEOUT;
+    loop {
+        match conn.poll_for_event().x()? {
+            Some(Event::PropertyNotify(e))
+                if e.window == win && e.atom == prop && e.state == Property::NEW_VALUE =>
+            {
+                let r = conn
+                    .get_property(true, win, prop, AtomEnum::ANY, 0, u32::MAX)
+                    .x()?
+                    .reply()
+                    .x()?;
+                if r.value.is_empty() {
+                    return Ok(data);
+                }
+                // the owner would send it all otherwise
+                super::fits(data.len() + r.value.len())?;
+                data.extend_from_slice(&r.value);
+                deadline = Instant::now() + TIMEOUT;
+            }
+            Some(_) => {}
+            None if Instant::now() > deadline => {
+                return Err(ClipError::Backend("selection owner stopped sending".into()))
+            }
+            None => std::thread::sleep(Duration::from_millis(5)),
+        }
+    }
+}
+
+/// something being sent with INCR, a piece each time the requestor deletes the last
+struct Sending {
+    target: Atom,
+    data: Arc<[u8]>,
+    sent: usize,
+}
+
+/// INCR transfers going on, by requestor and property
+type Transfers = HashMap<(Window, Atom), Sending>;
+
+/// how much goes in a property at once, past which it's sent with INCR
+fn chunk(conn: &RustConnection) -> usize {
+    // well under the biggest request, as the request around it takes some too
+    (conn.maximum_request_bytes() / 4).min(1 << 20)
+}
+
+fn serve(c: &Conn, offers: &Mutex<Offers>, atoms: Atoms) {
+    let mut sending = Transfers::new();
+    while let Ok(e) = c.conn.wait_for_event() {
+        match e {
+            Event::SelectionRequest(e) => {
+                // lazy data is made with the lock let go
+                let offered = offers.lock().unwrap().get(&e.selection).cloned();
+                _ = respond(&c.conn, offered.as_deref(), atoms, &e, &mut sending);
+            }
+            Event::PropertyNotify(e) if e.state == Property::DELETE => {
+                if let Some(s) = sending.get_mut(&(e.window, e.atom)) {
+                    let done = s.sent == s.data.len();
+                    _ = send(&c.conn, &e, s);
+                    if done {
+                        sending.remove(&(e.window, e.atom));
+                        if !sending.keys().any(|&(w, _)| w == e.window) {
+                            // done with its window
+                            _ = c.conn.change_window_attributes(
+                                e.window,
+                                &ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT),
+                            );
+                            _ = c.conn.flush();
+                        }
+                    }
+                }
+            }
+            Event::SelectionClear(e) if e.owner == c.win => {
+                offers.lock().unwrap().remove(&e.selection);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// the next piece of `s`, or the empty one that ends it
+fn send(
+    conn: &RustConnection,
+    e: &PropertyNotifyEvent,
+    s: &mut Sending,
+) -> std::result::Result<(), x11rb::errors::ConnectionError> {
+    let end = (s.sent + chunk(conn)).min(s.data.len());
+    conn.change_property8(
+        PropMode::REPLACE,
+        e.window,
+        e.atom,
+        s.target,
+        &s.data[s.sent..end],
+    )?;
+    s.sent = end;
+    conn.flush()
+}
+
+fn respond(
+    conn: &RustConnection,
+    offers: Option<&[(Atom, Lazy)]>,
+    atoms: Atoms,
+    e: &SelectionRequestEvent,
+    sending: &mut Transfers,
+) -> std::result::Result<(), x11rb::errors::ConnectionError> {
+    // obsolete clients
+    let property = if e.property == NONE {
+        e.target
+    } else {
+        e.property
+    };
+    let ok = match offers {
+        Some(offers) if e.target == atoms.TARGETS => {
+            let targets = offers
+                .iter()
+                .map(|&(a, _)| a)
+                .chain([atoms.TARGETS])
+                .collect::<Vec<_>>();
+            conn.change_property32(
+                PropMode::REPLACE,
+                e.requestor,
+                property,
+                AtomEnum::ATOM,
+                &targets,
+            )?;
+            true
+        }
+        Some(offers) => match offers.iter().find(|&&(a, _)| a == e.target) {
+            Some((a, d)) => {
+                let data = d.get();
+                if data.len() > chunk(conn) {
+                    // too big for one property: its size now, and the pieces as the requestor deletes each
+                    conn.change_window_attributes(
+                        e.requestor,
+                        &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+                    )?;
+                    conn.change_property32(
+                        PropMode::REPLACE,
+                        e.requestor,
+                        property,
+                        atoms.INCR,
+                        &[u32::try_from(data.len()).unwrap_or(u32::MAX)],
+                    )?;
+                    sending.insert(
+                        (e.requestor, property),
+                        Sending {
+                            target: *a,
+                            data,
+                            sent: 0,
+                        },
+                    );
+                } else {
+                    conn.change_property8(PropMode::REPLACE, e.requestor, property, *a, &data)?;
+                }
+                true
+            }
+            None => false,
+        },
+        None => false,
+    };
+    conn.send_event(
+        false,
+        e.requestor,
+        EventMask::NO_EVENT,
+        SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: e.time,
+            requestor: e.requestor,
+            selection: e.selection,
+            target: e.target,
+            property: if ok { property } else { NONE },
+        },
+    )?;
+    conn.flush()
+}
+
+/// a selection owner change per item, until the flag's set
+struct Changes(Conn, Arc<AtomicBool>);
+
+impl Iterator for Changes {
+    type Item = ();
+
+    fn next(&mut self) -> Option<()> {
+        // waiting for an event can't be interrupted, so the stop flag is looked at in between looking for them
+        while !self.1.load(Ordering::Relaxed) {
+            match self.0.conn.poll_for_event().ok()? {
+                Some(Event::XfixesSelectionNotify(_)) => return Some(()),
+                Some(_) => {}
+                None => {
+                    crate::watch::nap(Duration::from_millis(50), &self.1);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Provider for X11 {
+    fn copy_bytes(&self, sel: Selection, bytes: &[u8]) -> Result<()> {
+        let b = Lazy::made(bytes.into());
+        self.own(sel, self.text().map(|t| (t, b.clone())).to_vec())
+    }
+
+    fn paste_bytes(&self, sel: Selection) -> Result<Vec<u8>> {
+        if let Some(b) = self.convert(sel, self.atoms.UTF8_STRING)? {
+            return Ok(b);
+        }
+        // latin 1
+        Ok(self
+            .convert(sel, AtomEnum::STRING.into())?
+            .map(|b| {
+                b.into_iter()
+                    .map(char::from)
+                    .collect::<String>()
+                    .into_bytes()
+            })
+            .unwrap_or_default())
+    }
+
+    fn clear(&self, sel: Selection) -> Result<()> {
+        self.own(sel, vec![])
+    }
+
+    fn copy_mime(&self, sel: Selection, mime: &str, bytes: &[u8]) -> Result<()> {
+        self.copy_formats(sel, &[(mime, bytes)])
+    }
+
+    fn formats(&self, sel: Selection) -> Result<Vec<String>> {
+        let conn = &self.server.conn;
+        self.targets(sel)?
+            .into_iter()
+            .filter(|&t| t != self.atoms.TARGETS)
+            .map(|t| {
+                let name = conn.get_atom_name(t).x()?.reply().x()?.name;
+                Ok(String::from_utf8_lossy(&name).into_owned())
+            })
+            .collect()
+    }
+
+    fn paste_mime(&self, sel: Selection, mime: &str) -> Result<Option<Vec<u8>>> {
+        if plain(mime) {
+            return self.paste_bytes(sel).map(Some);
+        }
+        let target = self.intern(mime)?;
+        if !self.targets(sel)?.contains(&target) {
+            return Ok(None);
+        }
+        self.convert(sel, target)
+    }
+
+    fn size(&self, sel: Selection, mime: &str) -> Result<Option<usize>> {
+        let target = if plain(mime) {
+            self.atoms.UTF8_STRING
+        } else {
+            self.intern(mime)?
+        };
+        let sel = self.atom(sel);
+        if self.owns(sel)? {
+            return Ok(self.offered(sel, target).map(|d| d.len()));
+        }
+        // the owner still puts it all on the server, but none of it comes here
+        let size = self.ask(sel, target, |conn, win, prop| {
+            let r = conn
+                .get_property(false, win, prop, AtomEnum::ANY, 0, 1)
+                .x()?
+                .reply()
+                .x()?;
+            conn.delete_property(win, prop).x()?;
+            conn.flush().x()?;
+            if r.type_ == self.atoms.INCR {
+                // what it says it'll send, which can be low
+                return Ok(r.value32().and_then(|mut v| v.next()).map(|n| n as usize));
+            }
+            let bytes = r.value_len * u32::from(r.format / 8);
+            Ok(Some(bytes as usize + r.bytes_after as usize))
+        })?;
+        Ok(size.flatten())
+    }
+
+    fn copy_formats(&self, sel: Selection, formats: &[(&str, &[u8])]) -> Result<()> {
+        let mut targets = vec![];
+        for &(mime, bytes) in formats {
+            let b = Lazy::made(bytes.into());
+            if plain(mime) {
+                targets.extend(self.text().map(|t| (t, b.clone())));
+            } else {
+                targets.push((self.intern(mime)?, b));
+            }
+        }
+        self.own(sel, targets)
+    }
+
+    fn copy_lazy(&self, sel: Selection, mime: &str, data: Lazy) -> Result<()> {
+        let targets = if plain(mime) {
+            self.text().map(|t| (t, data.clone())).to_vec()
+        } else {
+            vec![(self.intern(mime)?, data)]
+        };
+        self.own(sel, targets)
+    }
+
+    fn changes(
+        &self,
+        sel: Selection,
+        _every: Duration,
+        stop: &Arc<AtomicBool>,
+    ) -> Option<Box<dyn Iterator<Item = ()> + Send>> {
+        let c = Conn::new(self.display.as_deref()).ok()?;
+        c.conn.xfixes_query_version(5, 0).ok()?.reply().ok()?;
+        c.conn
+            .xfixes_select_selection_input(
+                c.win,
+                self.atom(sel),
+                SelectionEventMask::SET_SELECTION_OWNER,
+            )
+            .ok()?;
+        c.conn.flush().ok()?;
+        Some(Box::new(Changes(c, Arc::clone(stop))))
+    }
+}
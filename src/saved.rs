@@ -0,0 +1,173 @@
+//! the clipboard kept for later: in memory by [`snapshot`](crate::snapshot), and on disk by [`save`](crate::save).
+//! on disk, it's a header, how many things, and for each, how many formats, then each mime type and its data,
+//! counts and lengths as 4 big endian bytes
+use crate::{providers, ClipError, Clipboard, Item, Result, Selection};
+use std::{fmt, io::ErrorKind};
+
+/// Everything that was on the clipboard, in every format, to put back later. See [`snapshot`](crate::snapshot).
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ClipboardSnapshot {
+    pub(crate) items: Vec<Item>,
+}
+
+impl ClipboardSnapshot {
+    /// What was there: each thing, in every format it was in.
+    #[must_use]
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// Whether the clipboard was empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Put it all back on the clipboard: every format, if the backend can hold them all at once,
+    /// or at least the text. An empty one clears it.
+    ///
+    /// # Errors
+    ///
+    /// If no clipboard is available, or the backend fails.
+    pub fn restore(&self) -> Result<()> {
+        self.restore_to(&crate::current()?)
+    }
+
+    /// Put it all back on `clip`. See [`restore`](Self::restore).
+    ///
+    /// # Errors
+    ///
+    /// If the backend fails.
+    pub fn restore_to(&self, clip: &Clipboard) -> Result<()> {
+        let formats: Vec<Vec<(&str, &[u8])>> = self
+            .items
+            .iter()
+            .map(|i| i.iter().map(|(m, d)| (&**m, &**d)).collect())
+            .collect();
+        let all: Vec<&[(&str, &[u8])]> = formats.iter().map(|f| &**f).collect();
+        match clip.copy_items(&all) {
+            // the text, at least
+            Err(ClipError::Unsupported) => {
+                let text = formats
+                    .iter()
+                    .flatten()
+                    .find(|(m, _)| providers::plain(m))
+                    .ok_or(ClipError::Unsupported)?;
+                clip.run(|p| p.copy_bytes(Selection::Clipboard, text.1))
+            }
+            r => r,
+        }
+    }
+}
+
+impl fmt::Debug for ClipboardSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // what was there could be anything, so only what it was in
+        let mimes: Vec<Vec<&str>> = self
+            .items
+            .iter()
+            .map(|i| i.iter().map(|(m, _)| &**m).collect())
+            .collect();
+        f.debug_struct("ClipboardSnapshot")
+            .field("formats", &mimes)
+            .finish_non_exhaustive()
+    }
+}
+
+const HEADER: &[u8] = b"clipp saved 1\n";
+
+fn put(out: &mut Vec<u8>, n: usize) -> Result<()> {
+    let n = u32::try_from(n).map_err(|_| ClipError::TooBig {
+        limit: u32::MAX as usize,
+    })?;
+    out.extend_from_slice(&n.to_be_bytes());
+    Ok(())
+}
+
+pub fn encode(items: &[Item]) -> Result<Vec<u8>> {
+    let mut out = HEADER.to_vec();
+    put(&mut out, items.len())?;
+    for item in items {
+        put(&mut out, item.len())?;
+        for (mime, data) in item {
+            for b in [mime.as_bytes(), data] {
+                put(&mut out, b.len())?;
+                out.extend_from_slice(b);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// the next `n` bytes
+fn take<'a>(b: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    let (took, rest) = b.split_at_checked(n)?;
+    *b = rest;
+    Some(took)
+}
+
+fn count(b: &mut &[u8]) -> Option<usize> {
+    let n = take(b, 4)?.try_into().ok()?;
+    usize::try_from(u32::from_be_bytes(n)).ok()
+}
+
+fn parse(mut b: &[u8]) -> Option<Vec<Item>> {
+    let b = &mut b;
+    if take(b, HEADER.len())? != HEADER {
+        return None;
+    }
+    (0..count(b)?)
+        .map(|_| {
+            (0..count(b)?)
+                .map(|_| {
+                    let n = count(b)?;
+                    let mime = String::from_utf8(take(b, n)?.to_vec()).ok()?;
+                    let n = count(b)?;
+                    Some((mime, take(b, n)?.to_vec()))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn decode(b: &[u8]) -> Result<Vec<Item>> {
+    parse(b).ok_or_else(|| {
+        ClipError::Io(std::io::Error::new(
+            ErrorKind::InvalidData,
+            "not a clipboard saved by clipp",
+        ))
+    })
+}
+
+#[test]
+fn round_trip() {
+    let items = vec![
+        vec![
+            ("text/html".to_owned(), b"<b>hi</b>".to_vec()),
+            ("text/plain".to_owned(), b"hi".to_vec()),
+        ],
+        vec![("image/png".to_owned(), vec![0, 1, 2])],
+    ];
+    let b = encode(&items).unwrap();
+    assert_eq!(decode(&b).unwrap(), items);
+    assert!(decode(&b[..b.len() - 1]).is_err());
+    assert!(decode(b"something else").is_err());
+    assert_eq!(decode(&encode(&[]).unwrap()).unwrap(), Vec::<Item>::new());
+}
+
+#[test]
+fn snapshot() {
+    crate::testing::with_mock(|mock| {
+        mock.set_data("text/html", b"<b>secret</b>");
+        let was = crate::snapshot();
+        assert_eq!(was.items()[0][0].0, "text/html");
+        assert!(!format!("{was:?}").contains("secret"));
+        crate::clear();
+        let empty = crate::snapshot();
+        assert!(empty.is_empty());
+        was.restore().unwrap();
+        assert_eq!(mock.data("text/html").unwrap(), b"<b>secret</b>");
+        empty.restore().unwrap();
+        assert!(mock.data("text/html").is_none());
+    });
+}
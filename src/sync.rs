@@ -0,0 +1,467 @@
+//! Share the clipboard between machines, over TCP. Opt in with the `sync` feature.
+//!
+//! Each end sends what's copied on it, and copies what the other end sends, so both clipboards
+//! hold the same thing from then on. Only text, and only the regular clipboard.
+//!
+//! Give both ends the same key, with [`set_key`] or `CLIPP_SYNC_KEY`, and everything is encrypted,
+//! and nothing sent without the key is copied. Without one, it all crosses the network as it is:
+//! passwords included.
+//!
+//! ```no_run
+//! // on one machine, for ever
+//! clipp::sync::listen("0.0.0.0:7878").unwrap();
+//! ```
+//!
+//! ```no_run
+//! // on another, until it's dropped
+//! let link = clipp::sync::connect("desktop:7878").unwrap();
+//! ```
+//!
+// discover is only there with it
+#![cfg_attr(
+    feature = "mdns",
+    doc = r"
+With the `mdns` feature, [`listen`] announces itself on the local network, and [`discover`] finds it,
+so nobody has to pass addresses around:
+
+```no_run
+# use std::time::Duration;
+let found = clipp::sync::discover(Duration::from_secs(2)).unwrap();
+let link = clipp::sync::connect(&found[0].addrs[..]).unwrap();
+```
+"
+)]
+//!
+//! `clipp sync listen <addr>` and `clipp sync connect <addr>` do the same from the shell,
+//! and `clipp sync connect` without an address connects to the first one it finds.
+//!
+//! Each message is the text's length as 4 big endian bytes, then the text.
+//! With a key, each end first sends 32 random bytes, and the text is sealed with ChaCha20-Poly1305,
+//! keyed by the SHA-256 of the key's, the sender's and the receiver's bytes, and numbered from 0.
+//! So each direction of each connection has a key of its own, and nothing can be replayed or reordered.
+use crate::{providers::fits, watch::hash, ClipError, Clipboard, Result};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    io::{BufReader, ErrorKind, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread::JoinHandle,
+};
+
+/// the hash of [`set_key`]'s key
+static KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+/// Encrypt everything sent with `key`, and refuse what isn't. Both ends need the same one.
+/// Without it, `CLIPP_SYNC_KEY` is the key, if it's set.
+/// Only connections made after count, and it's only as good as it's long: a passphrase of a few words, at least.
+pub fn set_key(key: Option<&str>) {
+    *KEY.lock().unwrap_or_else(PoisonError::into_inner) = key.map(derive);
+}
+
+fn derive(key: &str) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(b"clipp sync ")
+        .chain_update(key)
+        .finalize()
+        .into()
+}
+
+fn key() -> Option<[u8; 32]> {
+    let set = *KEY.lock().unwrap_or_else(PoisonError::into_inner);
+    set.or_else(|| std::env::var("CLIPP_SYNC_KEY").ok().map(|k| derive(&k)))
+}
+
+/// one direction of a connection, with a key
+struct Seal {
+    cipher: ChaCha20Poly1305,
+    /// how many messages went through
+    n: u64,
+}
+
+impl Seal {
+    fn new(key: &[u8; 32], from: &[u8; 32], to: &[u8; 32]) -> Self {
+        let k: [u8; 32] = Sha256::new()
+            .chain_update(key)
+            .chain_update(from)
+            .chain_update(to)
+            .finalize()
+            .into();
+        Self {
+            cipher: ChaCha20Poly1305::new(&k.into()),
+            n: 0,
+        }
+    }
+
+    fn nonce(&mut self) -> [u8; 12] {
+        let mut n = [0; 12];
+        n[..8].copy_from_slice(&self.n.to_le_bytes());
+        self.n += 1;
+        n
+    }
+
+    fn seal(&mut self, text: &[u8]) -> Result<Vec<u8>> {
+        let n = self.nonce();
+        self.cipher
+            .encrypt(&n.into(), text)
+            .map_err(|_| ClipError::TooBig {
+                limit: u32::MAX as usize,
+            })
+    }
+
+    fn open(&mut self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let n = self.nonce();
+        self.cipher.decrypt(&n.into(), sealed).map_err(|_| {
+            ClipError::Io(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "the other end has another key, or none",
+            ))
+        })
+    }
+}
+
+/// trade random bytes, and make the [`Seal`]s for sending and receiving, if there's a key
+fn handshake(stream: &TcpStream) -> Result<(Option<Seal>, Option<Seal>)> {
+    let Some(key) = key() else {
+        return Ok((None, None));
+    };
+    let mut ours = [0; 32];
+    getrandom::fill(&mut ours).map_err(|e| ClipError::Io(e.into()))?;
+    let mut theirs = [0; 32];
+    let mut s = stream;
+    s.write_all(&ours)
+        .and_then(|()| s.read_exact(&mut theirs))
+        .map_err(ClipError::Io)?;
+    Ok((
+        Some(Seal::new(&key, &ours, &theirs)),
+        Some(Seal::new(&key, &theirs, &ours)),
+    ))
+}
+
+/// Shares the clipboard with one other machine, until it's dropped. See [`connect`] and [`share`].
+#[must_use = "sharing stops as soon as this is dropped"]
+#[derive(Debug)]
+pub struct Link {
+    stream: TcpStream,
+    stop: Arc<AtomicBool>,
+    sending: Option<JoinHandle<()>>,
+    receiving: Option<JoinHandle<Result<()>>>,
+}
+
+impl Link {
+    /// Block until the other end hangs up.
+    ///
+    /// # Errors
+    ///
+    /// If it sent something that isn't text, or copying what it sent fails.
+    pub fn wait(mut self) -> Result<()> {
+        let Some(receiving) = self.receiving.take() else {
+            return Ok(());
+        };
+        receiving
+            .join()
+            .unwrap_or_else(|p| std::panic::resume_unwind(p))
+    }
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        _ = self.stream.shutdown(Shutdown::Both);
+        if let Some(s) = self.sending.take() {
+            _ = s.join();
+        }
+    }
+}
+
+/// Share the clipboard with whatever's at `addr`, i.e. another machine's [`listen`].
+///
+/// # Errors
+///
+/// If no clipboard is available, or it can't connect.
+/// If the other end has another key, [`Link::wait`] says so once it sends something.
+pub fn connect(addr: impl ToSocketAddrs) -> Result<Link> {
+    share(TcpStream::connect(addr).map_err(ClipError::Io)?)
+}
+
+/// Share the clipboard over a connection made some other way.
+///
+/// # Errors
+///
+/// If no clipboard is available, the other end hangs up before trading bytes for the key, or the threads can't be started.
+pub fn share(stream: TcpStream) -> Result<Link> {
+    let clip = crate::current()?;
+    let received = Arc::new(AtomicU64::new(0));
+    let (send, recv) = handshake(&stream)?;
+    let (stop, sending) = sending(&clip, &stream, send, &received)?;
+    let receiving = {
+        let stream = stream.try_clone().map_err(ClipError::Io)?;
+        std::thread::Builder::new()
+            .name("clipp sync receive".into())
+            .spawn(move || receive(&clip, &stream, recv, &received))
+            .map_err(ClipError::Io)?
+    };
+    Ok(Link {
+        stream,
+        stop,
+        sending: Some(sending),
+        receiving: Some(receiving),
+    })
+}
+
+/// Take connections on `addr` for ever, sharing the clipboard with everything that connects.
+/// What one of them copies goes to the others too.
+/// With the `mdns` feature, it's announced on the local network for [`discover`] to find.
+///
+/// # Errors
+///
+/// If no clipboard is available, or it can't listen on `addr`, or announce it.
+pub fn listen(addr: impl ToSocketAddrs) -> Result<()> {
+    let clip = crate::current()?;
+    let listener = TcpListener::bind(addr).map_err(ClipError::Io)?;
+    #[cfg(feature = "mdns")]
+    let _announced = Announced::new(listener.local_addr().map_err(ClipError::Io)?.port())?;
+    for stream in listener.incoming() {
+        // one failed connection shouldn't end the rest
+        let Ok(stream) = stream else { continue };
+        let clip = clip.clone();
+        _ = std::thread::Builder::new()
+            .name("clipp sync peer".into())
+            .spawn(move || {
+                let received = Arc::new(AtomicU64::new(0));
+                if let Ok((send, recv)) = handshake(&stream) {
+                    if let Ok((stop, _)) = sending(&clip, &stream, send, &received) {
+                        _ = receive(&clip, &stream, recv, &received);
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                }
+                event!(debug, "sync peer {:?} left", stream.peer_addr());
+            });
+    }
+    Ok(())
+}
+
+/// what clipp sync announces itself as
+#[cfg(feature = "mdns")]
+const SERVICE: &str = "_clipp-sync._tcp.local.";
+
+#[cfg(feature = "mdns")]
+fn m(e: mdns_sd::Error) -> ClipError {
+    ClipError::Backend(Box::new(e))
+}
+
+/// Another machine's [`listen`], found by [`discover`].
+#[cfg(feature = "mdns")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Peer {
+    /// What it calls itself: its host name, and its process id.
+    pub name: String,
+    /// Where it listens, ipv4 first. [`connect`] takes them as they are.
+    pub addrs: Vec<std::net::SocketAddr>,
+}
+
+/// Look for other machines' [`listen`]s on the local network for `wait`.
+///
+/// # Errors
+///
+/// If multicast dns can't be used, i.e. there's no network.
+#[cfg(feature = "mdns")]
+pub fn discover(wait: std::time::Duration) -> Result<Vec<Peer>> {
+    use mdns_sd::{ServiceDaemon, ServiceEvent};
+    let mdns = ServiceDaemon::new().map_err(m)?;
+    let events = mdns.browse(SERVICE).map_err(m)?;
+    let until = std::time::Instant::now() + wait;
+    let mut found = Vec::<Peer>::new();
+    while let Ok(e) = events.recv_deadline(until) {
+        let ServiceEvent::ServiceResolved(s) = e else {
+            continue;
+        };
+        let name = s.fullname.strip_suffix(SERVICE).unwrap_or(&s.fullname);
+        let name = name.strip_suffix('.').unwrap_or(name).to_owned();
+        let mut addrs: Vec<_> = s
+            .get_addresses()
+            .iter()
+            .map(|ip| std::net::SocketAddr::new(ip.to_ip_addr(), s.port))
+            .collect();
+        // link local ipv6 needs a scope that's lost here
+        addrs.sort_by_key(std::net::SocketAddr::is_ipv6);
+        // it's resolved again when it changes
+        found.retain(|p| p.name != name);
+        found.push(Peer { name, addrs });
+    }
+    _ = mdns.shutdown();
+    Ok(found)
+}
+
+/// [`listen`]'s announcement, until it's dropped
+#[cfg(feature = "mdns")]
+struct Announced(mdns_sd::ServiceDaemon);
+
+#[cfg(feature = "mdns")]
+impl Announced {
+    fn new(port: u16) -> Result<Self> {
+        let host = std::env::var("HOSTNAME")
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .ok()
+            .or_else(|| {
+                let h = std::fs::read_to_string("/etc/hostname").ok()?;
+                Some(h.trim().to_owned())
+            })
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "clipp".into());
+        let info = mdns_sd::ServiceInfo::new(
+            SERVICE,
+            &format!("{host} {}", std::process::id()),
+            &format!("{host}.local."),
+            "",
+            port,
+            None,
+        )
+        .map_err(m)?
+        .enable_addr_auto();
+        let mdns = mdns_sd::ServiceDaemon::new().map_err(m)?;
+        mdns.register(info).map_err(m)?;
+        event!(debug, "announced sync on port {port}");
+        Ok(Self(mdns))
+    }
+}
+
+#[cfg(feature = "mdns")]
+impl Drop for Announced {
+    fn drop(&mut self) {
+        _ = self.0.shutdown();
+    }
+}
+
+/// send what's copied, except what was just `received`, on a thread that ends once the flag is set
+fn sending(
+    clip: &Clipboard,
+    stream: &TcpStream,
+    mut seal: Option<Seal>,
+    received: &Arc<AtomicU64>,
+) -> Result<(Arc<AtomicBool>, JoinHandle<()>)> {
+    // made here so nothing copied after connecting is missed
+    let watch = clip.watch();
+    let stop = watch.stopper();
+    let mut stream = stream.try_clone().map_err(ClipError::Io)?;
+    let received = Arc::clone(received);
+    let t = std::thread::Builder::new()
+        .name("clipp sync send".into())
+        .spawn(move || {
+            for text in watch {
+                // what the other end sent, coming back
+                if hash(&text) == received.load(Ordering::Relaxed) {
+                    continue;
+                }
+                if write(&mut stream, &text, seal.as_mut()).is_err() {
+                    break;
+                }
+            }
+        })
+        .map_err(ClipError::Io)?;
+    Ok((stop, t))
+}
+
+fn write(w: &mut impl Write, text: &str, seal: Option<&mut Seal>) -> Result<()> {
+    let sealed;
+    let text = match seal {
+        Some(s) => {
+            sealed = s.seal(text.as_bytes())?;
+            &sealed
+        }
+        None => text.as_bytes(),
+    };
+    let len = u32::try_from(text.len()).map_err(|_| ClipError::TooBig {
+        limit: u32::MAX as usize,
+    })?;
+    w.write_all(&len.to_be_bytes())
+        .and_then(|()| w.write_all(text))
+        .and_then(|()| w.flush())
+        .map_err(ClipError::Io)
+}
+
+/// the next message, or [`None`] once the other end hangs up
+fn read(r: &mut impl Read, seal: Option<&mut Seal>) -> Result<Option<String>> {
+    let mut len = [0; 4];
+    match r.read_exact(&mut len) {
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        r => r.map_err(ClipError::Io)?,
+    }
+    let len = u32::from_be_bytes(len);
+    fits(usize::try_from(len).unwrap_or(usize::MAX))?;
+    let mut text = vec![];
+    r.take(len.into())
+        .read_to_end(&mut text)
+        .map_err(ClipError::Io)?;
+    if text.len() < len as usize {
+        return Err(ClipError::Io(ErrorKind::UnexpectedEof.into()));
+    }
+    if let Some(s) = seal {
+        text = s.open(&text)?;
+    }
+    String::from_utf8(text).map(Some).map_err(ClipError::Utf8)
+}
+
+/// copy what the other end sends until it hangs up
+fn receive(
+    clip: &Clipboard,
+    stream: &TcpStream,
+    mut seal: Option<Seal>,
+    received: &AtomicU64,
+) -> Result<()> {
+    let mut r = BufReader::new(stream);
+    while let Some(text) = read(&mut r, seal.as_mut())? {
+        received.store(hash(&text), Ordering::Relaxed);
+        clip.copy(&text)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn messages() {
+    let mut wire = vec![];
+    write(&mut wire, "one", None).unwrap();
+    write(&mut wire, "", None).unwrap();
+    assert_eq!(&wire[..7], b"\0\0\0\x03one");
+    let mut r = &wire[..];
+    assert_eq!(read(&mut r, None).unwrap().as_deref(), Some("one"));
+    assert_eq!(read(&mut r, None).unwrap().as_deref(), Some(""));
+    assert_eq!(read(&mut r, None).unwrap(), None);
+    // cut off partway
+    assert!(read(&mut &wire[..5], None).is_err());
+}
+
+#[test]
+fn sealed() {
+    let (key, a, b) = (derive("hunter2"), [1; 32], [2; 32]);
+    let mut wire = vec![];
+    let mut send = Seal::new(&key, &a, &b);
+    write(&mut wire, "secret", Some(&mut send)).unwrap();
+    write(&mut wire, "another", Some(&mut send)).unwrap();
+    assert!(!wire.windows(6).any(|w| w == b"secret"));
+    let mut r = &wire[..];
+    let mut recv = Seal::new(&key, &a, &b);
+    assert_eq!(
+        read(&mut r, Some(&mut recv)).unwrap().as_deref(),
+        Some("secret")
+    );
+    assert_eq!(
+        read(&mut r, Some(&mut recv)).unwrap().as_deref(),
+        Some("another")
+    );
+    // the first one again
+    assert!(read(&mut &wire[..], Some(&mut recv)).is_err());
+    // another key, or the other direction
+    for mut s in [
+        Seal::new(&derive("hunter3"), &a, &b),
+        Seal::new(&key, &b, &a),
+    ] {
+        assert!(read(&mut &wire[..], Some(&mut s)).is_err());
+    }
+}
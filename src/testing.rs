@@ -0,0 +1,102 @@
+//! A fake clipboard, for testing code that copies and pastes.
+use crate::{
+    providers::{Chosen, Memory, Provider},
+    Clipboard, Selection,
+};
+use std::{cell::RefCell, sync::Arc};
+
+thread_local! {
+    static MOCK: RefCell<Option<Clipboard>> = const { RefCell::new(None) };
+}
+
+/// the mock on this thread, if there is one
+pub(crate) fn mocked() -> Option<Clipboard> {
+    MOCK.with(|m| m.borrow().clone())
+}
+
+/// The clipboard [`with_mock`] swaps in. Starts out empty.
+pub struct Mock {
+    memory: Arc<Memory>,
+}
+
+impl Mock {
+    /// The text on the clipboard. [`None`] if there is none, or it isn't UTF-8.
+    #[must_use]
+    pub fn text(&self) -> Option<String> {
+        String::from_utf8(self.data("text/plain")?).ok()
+    }
+
+    /// The data of the given mime type on the clipboard, if there is any.
+    #[must_use]
+    pub fn data(&self, mime: &str) -> Option<Vec<u8>> {
+        self.memory
+            .paste_mime(Selection::Clipboard, mime)
+            .ok()
+            .flatten()
+    }
+
+    /// Put text on the clipboard, as if something else copied it.
+    pub fn set_text(&self, text: &str) {
+        self.set_data("text/plain", text.as_bytes());
+    }
+
+    /// Put data of the given mime type on the clipboard, as if something else copied it.
+    pub fn set_data(&self, mime: &str, data: &[u8]) {
+        _ = self.memory.copy_mime(Selection::Clipboard, mime, data);
+    }
+
+    /// Empty the clipboard.
+    pub fn clear(&self) {
+        _ = self.memory.clear(Selection::Clipboard);
+    }
+}
+
+/// Run `f` with a fake clipboard in place of the real one, on this thread.
+/// Everything clipp does inside (the free functions, [`Clipboard::new`]) uses the fake.
+/// The real clipboard is left alone.
+///
+/// ```
+/// clipp::testing::with_mock(|mock| {
+///     mock.set_text("copied elsewhere");
+///     assert_eq!(clipp::paste(), "copied elsewhere");
+///     clipp::copy("hello");
+///     assert_eq!(mock.text().as_deref(), Some("hello"));
+/// });
+/// ```
+pub fn with_mock<R>(f: impl FnOnce(&Mock) -> R) -> R {
+    /// puts back whatever was there, even if `f` panics
+    struct Restore(Option<Clipboard>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            MOCK.with(|m| *m.borrow_mut() = self.0.take());
+        }
+    }
+
+    let memory = Arc::new(Memory::default());
+    let clip = Clipboard::of(Chosen {
+        clip: Arc::clone(&memory) as _,
+        name: "mock",
+        why: "with_mock was called",
+        falls_back: false,
+    });
+    let _restore = Restore(MOCK.with(|m| m.replace(Some(clip))));
+    f(&Mock { memory })
+}
+
+#[test]
+fn mock() {
+    with_mock(|outer| {
+        crate::copy("outer");
+        with_mock(|inner| {
+            assert_eq!(inner.text(), None);
+            assert_eq!(crate::backend_name(), Some("mock"));
+            assert!(crate::is_available());
+            crate::copy("inner");
+            assert_eq!(inner.text().as_deref(), Some("inner"));
+        });
+        assert_eq!(outer.text().as_deref(), Some("outer"));
+        crate::try_clear().unwrap();
+        assert_eq!(outer.text(), None);
+    });
+    assert!(mocked().is_none());
+}
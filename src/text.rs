@@ -0,0 +1,194 @@
+//! what [`copy`](crate::copy) takes, and what happens to text on the way
+use crate::Op;
+use std::{
+    borrow::Cow,
+    fmt::Arguments,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+/// Things [`copy`](crate::copy) can take. Strings are passed through as they are, without allocating;
+/// anything else that's [`Display`](std::fmt::Display) can go through [`format_args!`].
+///
+/// ```
+/// # let n = 4;
+/// clipp::copy("a str");
+/// clipp::copy(String::from("a string"));
+/// clipp::copy(n);
+/// clipp::copy(format_args!("{n} apples"));
+/// # assert_eq!(clipp::paste(), "4 apples");
+/// ```
+pub trait ToClip {
+    /// The text.
+    fn to_clip(&self) -> Cow<'_, str>;
+}
+
+impl ToClip for str {
+    fn to_clip(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl ToClip for String {
+    fn to_clip(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl ToClip for Cow<'_, str> {
+    fn to_clip(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl ToClip for Box<str> {
+    fn to_clip(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl ToClip for Rc<str> {
+    fn to_clip(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl ToClip for Arc<str> {
+    fn to_clip(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl ToClip for Arguments<'_> {
+    fn to_clip(&self) -> Cow<'_, str> {
+        self.as_str()
+            .map_or_else(|| Cow::Owned(self.to_string()), Cow::Borrowed)
+    }
+}
+
+impl<T: ToClip + ?Sized> ToClip for &T {
+    fn to_clip(&self) -> Cow<'_, str> {
+        (**self).to_clip()
+    }
+}
+
+impl<T: ToClip + ?Sized> ToClip for &mut T {
+    fn to_clip(&self) -> Cow<'_, str> {
+        (**self).to_clip()
+    }
+}
+
+macro_rules! shown {
+    ($($t:ty)+) => {$(
+        impl ToClip for $t {
+            fn to_clip(&self) -> Cow<'_, str> {
+                Cow::Owned(self.to_string())
+            }
+        }
+    )+};
+}
+shown!(char bool u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64);
+
+/// Line endings. See [`set_line_endings`](crate::set_line_endings).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// `\n`, as on unix.
+    Lf,
+    /// `\r\n`, as on windows.
+    CrLf,
+}
+
+/// by [`Op`]. 0 leaves them be, 1 is [`LineEnding::Lf`], 2 [`LineEnding::CrLf`]
+static ENDINGS: [AtomicU8; 2] = [AtomicU8::new(0), AtomicU8::new(0)];
+
+pub(crate) fn set_line_endings(op: Op, ending: Option<LineEnding>) {
+    let n = match ending {
+        None => 0,
+        Some(LineEnding::Lf) => 1,
+        Some(LineEnding::CrLf) => 2,
+    };
+    ENDINGS[op as usize].store(n, Ordering::Relaxed);
+}
+
+/// `text` with its line endings made `ending`
+fn convert(text: &str, ending: LineEnding) -> Cow<'_, str> {
+    match ending {
+        LineEnding::Lf if text.contains("\r\n") => Cow::Owned(text.replace("\r\n", "\n")),
+        LineEnding::CrLf
+            if text
+                .match_indices('\n')
+                .any(|(i, _)| !text[..i].ends_with('\r')) =>
+        {
+            Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+        }
+        _ => Cow::Borrowed(text),
+    }
+}
+
+/// `text` with the line endings set for `op`
+pub(crate) fn endings(op: Op, text: &str) -> Cow<'_, str> {
+    match ENDINGS[op as usize].load(Ordering::Relaxed) {
+        1 => convert(text, LineEnding::Lf),
+        2 => convert(text, LineEnding::CrLf),
+        _ => Cow::Borrowed(text),
+    }
+}
+
+/// [`endings`], for text we have already
+pub(crate) fn endings_owned(op: Op, text: String) -> String {
+    match endings(op, &text) {
+        Cow::Owned(t) => t,
+        Cow::Borrowed(_) => text,
+    }
+}
+
+/// `bytes` as UTF-8, or, if they aren't, in the legacy encoding they look most like
+#[cfg(feature = "encoding")]
+pub(crate) fn decode(bytes: Vec<u8>) -> String {
+    String::from_utf8(bytes).unwrap_or_else(|e| {
+        let bytes = e.as_bytes();
+        let mut d = chardetng::EncodingDetector::new();
+        d.feed(bytes, true);
+        d.guess(None, false).decode(bytes).0.into_owned()
+    })
+}
+
+#[test]
+fn borrows() {
+    let s = String::from("hi");
+    assert!(matches!(s.to_clip(), Cow::Borrowed("hi")));
+    assert!(matches!((&&*s).to_clip(), Cow::Borrowed("hi")));
+    assert!(matches!(format_args!("hi").to_clip(), Cow::Borrowed("hi")));
+    assert_eq!(format_args!("{s}!").to_clip(), "hi!");
+    assert_eq!(4.to_clip(), "4");
+}
+
+#[test]
+fn line_endings() {
+    assert_eq!(convert("a\r\nb\nc", LineEnding::Lf), "a\nb\nc");
+    assert_eq!(convert("a\r\nb\nc", LineEnding::CrLf), "a\r\nb\r\nc");
+    assert!(matches!(
+        convert("a\r\nb", LineEnding::CrLf),
+        Cow::Borrowed(_)
+    ));
+    assert!(matches!(
+        convert("a\nb\r", LineEnding::Lf),
+        Cow::Borrowed(_)
+    ));
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn legacy() {
+    assert_eq!(decode("already utf8 é".into()), "already utf8 é");
+    // what notepad saved as ansi, on a western machine
+    assert_eq!(decode(b"caf\xe9 \x93quoted\x94".to_vec()), "café “quoted”");
+    // and on a japanese one
+    assert_eq!(
+        decode(b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd\x90\xa2\x8a\x45".to_vec()),
+        "こんにちは世界"
+    );
+}
@@ -0,0 +1,195 @@
+//! `text/uri-list` encoding, for file lists
+use std::{
+    borrow::Cow,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+#[cfg(unix)]
+fn bytes(p: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(p.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn bytes(p: &Path) -> Cow<'_, [u8]> {
+    Cow::Owned(p.to_string_lossy().replace('\\', "/").into_bytes())
+}
+
+#[cfg(unix)]
+fn path(b: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    std::ffi::OsString::from_vec(b).into()
+}
+
+#[cfg(not(unix))]
+fn path(b: Vec<u8>) -> PathBuf {
+    let s =
+        String::from_utf8(b).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
+    // /C:/x
+    match s.strip_prefix('/') {
+        Some(d) if d.as_bytes().get(1) == Some(&b':') => d.into(),
+        _ => s.into(),
+    }
+}
+
+/// `file://` uri for an absolute path
+pub fn encode(p: &Path) -> String {
+    let mut s = String::from("file://");
+    let b = bytes(p);
+    // windows paths start with the drive
+    if b.first() != Some(&b'/') {
+        s.push('/');
+    }
+    for &b in &*b {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                s.push(b as char);
+            }
+            b => write!(s, "%{b:02X}").unwrap(),
+        }
+    }
+    s
+}
+
+/// What pasting files in a file manager does with them. See [`cut_files`](crate::cut_files).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FileOp {
+    /// Copies them.
+    #[default]
+    Copy,
+    /// Moves them.
+    Cut,
+}
+
+/// `x-special/gnome-copied-files`: `copy` or `cut`, then a uri per line
+pub fn gnome(paths: &[impl AsRef<Path>], op: FileOp) -> std::io::Result<String> {
+    let mut s = String::from(match op {
+        FileOp::Copy => "copy",
+        FileOp::Cut => "cut",
+    });
+    for p in paths {
+        s.push('\n');
+        s.push_str(&encode(&std::path::absolute(p)?));
+    }
+    Ok(s)
+}
+
+/// the paths in `x-special/gnome-copied-files`, and what it says to do with them
+pub fn parse_gnome(data: &[u8]) -> (Vec<PathBuf>, FileOp) {
+    let op = match data.split(|&b| b == b'\n').next().map(<[u8]>::trim_ascii) {
+        Some(b"cut") => FileOp::Cut,
+        _ => FileOp::Copy,
+    };
+    // the first line isn't a file uri, so it's skipped
+    (parse(data), op)
+}
+
+/// uri list, one uri per line
+pub fn list(paths: &[impl AsRef<Path>]) -> std::io::Result<String> {
+    let mut s = String::new();
+    for p in paths {
+        s.push_str(&encode(&std::path::absolute(p)?));
+        s.push_str("\r\n");
+    }
+    Ok(s)
+}
+
+fn unhex(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// the path in a `file:` uri: `file:///x`, `file://localhost/x`, or `file:/x` as java and kde write it.
+/// other hosts are unc paths on windows, and left out elsewhere
+pub fn decode(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file:")?;
+    let p = match rest.strip_prefix("//") {
+        Some(r) => {
+            let (host, p) = r.find('/').map_or((r, ""), |i| r.split_at(i));
+            match host {
+                "" | "localhost" => p,
+                #[cfg(target_family = "windows")]
+                _ => return Some(unc(host, &unescape(p.as_bytes()))),
+                #[cfg(not(target_family = "windows"))]
+                _ => return None,
+            }
+        }
+        None if rest.starts_with('/') => rest,
+        None => return None,
+    };
+    Some(path(unescape(p.as_bytes())))
+}
+
+/// `\\host\share\x`
+#[cfg(target_family = "windows")]
+fn unc(host: &str, p: &[u8]) -> PathBuf {
+    let p = String::from_utf8_lossy(p).replace('/', "\\");
+    format!("\\\\{host}{p}").into()
+}
+
+/// undo the %XX escapes
+fn unescape(p: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(p.len());
+    let mut i = 0;
+    while i < p.len() {
+        if p[i] == b'%' {
+            let hex = |i: usize| p.get(i).copied().and_then(unhex);
+            if let (Some(h), Some(l)) = (hex(i + 1), hex(i + 2)) {
+                out.push(h << 4 | l);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(p[i]);
+        i += 1;
+    }
+    out
+}
+
+/// paths in a uri list, skipping comments and non file uris
+pub fn parse(list: &[u8]) -> Vec<PathBuf> {
+    String::from_utf8_lossy(list)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(decode)
+        .collect()
+}
+
+#[test]
+fn gnome_copied_files() {
+    let s = gnome(&["/a b", "/c"], FileOp::Cut).unwrap();
+    assert_eq!(s, "cut\nfile:///a%20b\nfile:///c");
+    assert_eq!(
+        parse_gnome(s.as_bytes()),
+        (vec![PathBuf::from("/a b"), "/c".into()], FileOp::Cut)
+    );
+    assert_eq!(parse_gnome(b"copy\n").1, FileOp::Copy);
+}
+
+#[test]
+fn hosts() {
+    assert_eq!(
+        decode("file://localhost/tmp/a%20b").unwrap(),
+        Path::new("/tmp/a b")
+    );
+    assert_eq!(decode("file:/tmp/x").unwrap(), Path::new("/tmp/x"));
+    assert_eq!(decode("file:tmp"), None);
+    #[cfg(not(target_family = "windows"))]
+    assert_eq!(decode("file://elsewhere/tmp/x"), None);
+}
+
+#[test]
+fn roundtrip() {
+    let p = Path::new("/tmp/hello wörld/100%.txt");
+    assert_eq!(encode(p), "file:///tmp/hello%20w%C3%B6rld/100%25.txt");
+    assert_eq!(
+        parse(format!("# comment\r\n{}\r\nhttps://x\r\n", encode(p)).as_bytes()),
+        [p]
+    );
+}
@@ -0,0 +1,253 @@
+//! clipboard change watching
+use crate::{ClipError, Clipboard, Provider, Result, Selection};
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+pub(crate) fn hash(s: &(impl Hash + ?Sized)) -> u64 {
+    let mut h = DefaultHasher::new();
+    s.hash(&mut h);
+    h.finish()
+}
+
+/// how long waiting goes between looking at the stop flag
+const SLICE: Duration = Duration::from_millis(50);
+
+/// sleep for `d`, or until `stop`. whether it wasn't stopped
+pub(crate) fn nap(d: Duration, stop: &AtomicBool) -> bool {
+    let mut left = d;
+    while !stop.load(Ordering::Relaxed) {
+        if left.is_zero() {
+            return true;
+        }
+        let s = left.min(SLICE);
+        std::thread::sleep(s);
+        left -= s;
+    }
+    false
+}
+
+/// run `f` on a thread of its own once `stop` is set, i.e. to kill what's blocking a change iterator
+#[cfg(any(
+    all(feature = "wayland", unix, not(target_os = "macos")),
+    all(target_family = "windows", feature = "windows-native")
+))]
+pub(crate) fn on_stop(stop: Arc<AtomicBool>, f: impl FnOnce() + Send + 'static) {
+    _ = std::thread::Builder::new()
+        .name("clipp watch stop".into())
+        .spawn(move || {
+            while nap(Duration::MAX, &stop) {}
+            f();
+        });
+}
+
+/// threads looping over a [`Watch`], stopped and waited for on drop
+#[derive(Debug)]
+struct Workers {
+    /// each one's [`Watch::stopper`]
+    stops: Vec<Arc<AtomicBool>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        for s in &self.stops {
+            s.store(true, Ordering::Relaxed);
+        }
+        for t in self.threads.drain(..) {
+            // dropped from inside, i.e. from the callback, which can't wait for itself
+            if t.thread().id() != std::thread::current().id() {
+                _ = t.join();
+            }
+        }
+    }
+}
+
+/// What the clipboard held at some point. See [`change_token`](crate::change_token).
+/// Only good for comparing with another one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChangeToken(pub(crate) u64);
+
+/// Copies whatever lands in the primary selection to the clipboard, and the other way around, until it's dropped.
+/// See [`sync_selections`](crate::sync_selections).
+#[must_use = "syncing stops as soon as this is dropped"]
+#[derive(Debug)]
+pub struct SelectionSync {
+    _workers: Workers,
+}
+
+impl SelectionSync {
+    pub(crate) fn start(clip: &Clipboard) -> Result<Self> {
+        // so it fails here if there is no primary selection
+        clip.paste_from(Selection::Primary)?;
+        let mut workers = Workers {
+            stops: vec![],
+            threads: vec![],
+        };
+        for (from, to) in [
+            (Selection::Primary, Selection::Clipboard),
+            (Selection::Clipboard, Selection::Primary),
+        ] {
+            let watch = clip.watch_from(from);
+            workers.stops.push(watch.stopper());
+            let clip = clip.clone();
+            let t = std::thread::Builder::new()
+                .name("clipp sync".into())
+                .spawn(move || {
+                    for text in watch {
+                        // deselecting empties primary, which shouldn't empty the clipboard.
+                        // copying it over changes the other one, which is then copied back: a no-op.
+                        if !text.is_empty() {
+                            _ = clip.copy_to(to, &text);
+                        }
+                    }
+                })
+                .map_err(ClipError::Io)?;
+            workers.threads.push(t);
+        }
+        Ok(Self { _workers: workers })
+    }
+}
+
+/// A change to the clipboard, as [`on_change`](crate::on_change) sees it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClipboardEvent {
+    /// The selection that changed.
+    pub selection: Selection,
+    /// What it holds now.
+    pub text: String,
+}
+
+/// Calls back whenever the clipboard changes, from a background thread, until it's dropped.
+/// See [`on_change`](crate::on_change).
+#[must_use = "the callback stops as soon as this is dropped"]
+#[derive(Debug)]
+pub struct Subscription {
+    _workers: Workers,
+}
+
+impl Subscription {
+    pub(crate) fn start(
+        watch: Watch,
+        mut f: impl FnMut(ClipboardEvent) + Send + 'static,
+    ) -> Result<Self> {
+        let stop = watch.stopper();
+        let selection = watch.sel;
+        let t = std::thread::Builder::new()
+            .name("clipp on_change".into())
+            .spawn(move || {
+                for text in watch {
+                    f(ClipboardEvent { selection, text });
+                }
+            })
+            .map_err(ClipError::Io)?;
+        Ok(Self {
+            _workers: Workers {
+                stops: vec![stop],
+                threads: vec![t],
+            },
+        })
+    }
+}
+
+/// Blocking iterator over clipboard changes, that stops (and stops whatever it started) when it's dropped.
+/// See [`watch`](crate::watch).
+pub struct Watch {
+    clip: Option<Arc<dyn Provider>>,
+    sel: Selection,
+    last: Option<u64>,
+    interval: Duration,
+    changes: Option<Box<dyn Iterator<Item = ()> + Send>>,
+    /// ends it, and `changes`, from anywhere
+    stop: Arc<AtomicBool>,
+}
+
+impl Watch {
+    pub(crate) fn new(clip: Option<Arc<dyn Provider>>, sel: Selection) -> Self {
+        let interval = Duration::from_millis(250);
+        let stop = Arc::new(AtomicBool::new(false));
+        let changes = clip.as_ref().and_then(|c| c.changes(sel, interval, &stop));
+        let mut this = Self {
+            clip,
+            sel,
+            last: None,
+            interval,
+            changes,
+            stop,
+        };
+        this.last = this.current().map(|s| hash(&s));
+        this
+    }
+
+    /// How often to check the clipboard, for backends that can't tell us when it changes.
+    /// On macos, that's how often the pasteboard's change count is read. Defaults to 250ms.
+    #[must_use]
+    pub fn every(mut self, interval: Duration) -> Self {
+        // the old ones stop, and the new ones get a flag of their own
+        self.stop.store(true, Ordering::Relaxed);
+        self.changes = None;
+        let stop = Arc::new(AtomicBool::new(false));
+        self.changes = self
+            .clip
+            .as_ref()
+            .and_then(|c| c.changes(self.sel, interval, &stop));
+        self.interval = interval;
+        self.stop = stop;
+        self
+    }
+
+    /// what ends it from another thread: [`next`](Iterator::next) returns [`None`] soon after it's set
+    pub(crate) fn stopper(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    fn current(&self) -> Option<String> {
+        self.clip.as_ref()?.paste(self.sel).ok()
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Iterator for Watch {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.clip.as_ref()?;
+        loop {
+            match &mut self.changes {
+                Some(c) => c.next()?,
+                None => {
+                    if !nap(self.interval, &self.stop) {
+                        return None;
+                    }
+                }
+            }
+            if self.stop.load(Ordering::Relaxed) {
+                return None;
+            }
+            let Some(s) = self.current() else {
+                self.last = None;
+                continue;
+            };
+            let h = hash(&s);
+            if self.last != Some(h) {
+                self.last = Some(h);
+                #[cfg(feature = "history")]
+                if self.sel == Selection::Clipboard {
+                    crate::history::record(&s);
+                }
+                return Some(s);
+            }
+        }
+    }
+}